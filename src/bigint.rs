@@ -0,0 +1,234 @@
+use std::cmp::Ordering;
+use std::ops::{Add, Mul, Neg, Sub};
+
+// Arbitrary precision integers, for numbers that do not fit in 32 bits.
+// The magnitude is kept in base 10^9 digits, least significant first, so the decimal form can be written digit by digit.
+const BASE: u64 = 1_000_000_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BigInt {
+  // Zero is never negative
+  negative: bool,
+  // Without leading zero digits, zero has no digits
+  digits: Vec<u32>,
+}
+
+impl BigInt {
+  fn new(negative: bool, mut digits: Vec<u32>) -> BigInt {
+    while digits.last() == Some(&0) {
+      digits.pop();
+    }
+    BigInt { negative: negative && !digits.is_empty(), digits }
+  }
+
+  pub fn is_zero(&self) -> bool {
+    self.digits.is_empty()
+  }
+
+  // The value as a number, if it fits in one
+  pub fn to_i32(&self) -> Option<i32> {
+    if self.digits.len() > 2 {
+      return None;
+    }
+    let magnitude = self.digits.iter().rev().fold(0i64, |value, digit| value * BASE as i64 + i64::from(*digit));
+    i32::try_from(if self.negative { -magnitude } else { magnitude }).ok()
+  }
+
+  // The quotient rounded towards zero like the division of numbers, None for a division by zero
+  pub fn checked_div(&self, divisor: &BigInt) -> Option<BigInt> {
+    if divisor.is_zero() {
+      return None;
+    }
+    Some(BigInt::new(self.negative != divisor.negative, divide(&self.digits, &divisor.digits)))
+  }
+}
+
+impl From<i64> for BigInt {
+  fn from(value: i64) -> BigInt {
+    let mut magnitude = value.unsigned_abs();
+    let mut digits = vec![];
+    while magnitude > 0 {
+      digits.push((magnitude % BASE) as u32);
+      magnitude /= BASE;
+    }
+    BigInt::new(value < 0, digits)
+  }
+}
+
+impl From<i32> for BigInt {
+  fn from(value: i32) -> BigInt {
+    BigInt::from(i64::from(value))
+  }
+}
+
+// Parses a decimal integer with an optional minus sign
+impl std::str::FromStr for BigInt {
+  type Err = String;
+
+  fn from_str(text: &str) -> Result<BigInt, String> {
+    let (negative, decimal) = match text.strip_prefix('-') {
+      Some(decimal) => (true, decimal),
+      None => (false, text),
+    };
+    if decimal.is_empty() || !decimal.bytes().all(|c| c.is_ascii_digit()) {
+      return Err(format!("'{}' is not an integer", text));
+    }
+    let digits = decimal.as_bytes().rchunks(9)
+      .map(|chunk| chunk.iter().fold(0, |digit, c| digit * 10 + u32::from(c - b'0')))
+      .collect();
+    Ok(BigInt::new(negative, digits))
+  }
+}
+
+impl std::fmt::Display for BigInt {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    if self.negative {
+      write!(f, "-")?;
+    }
+    match self.digits.split_last() {
+      None => write!(f, "0"),
+      Some((first, rest)) => {
+        write!(f, "{}", first)?;
+        rest.iter().rev().try_for_each(|digit| write!(f, "{:09}", digit))
+      }
+    }
+  }
+}
+
+impl Ord for BigInt {
+  fn cmp(&self, other: &BigInt) -> Ordering {
+    match (self.negative, other.negative) {
+      (false, true) => Ordering::Greater,
+      (true, false) => Ordering::Less,
+      (false, false) => compare(&self.digits, &other.digits),
+      (true, true) => compare(&other.digits, &self.digits),
+    }
+  }
+}
+
+impl PartialOrd for BigInt {
+  fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Neg for &BigInt {
+  type Output = BigInt;
+
+  fn neg(self) -> BigInt {
+    BigInt::new(!self.negative, self.digits.clone())
+  }
+}
+
+impl Add for &BigInt {
+  type Output = BigInt;
+
+  fn add(self, other: &BigInt) -> BigInt {
+    if self.negative == other.negative {
+      return BigInt::new(self.negative, add(&self.digits, &other.digits));
+    }
+    // The sign of the result is the sign of the operand with the larger magnitude
+    match compare(&self.digits, &other.digits) {
+      Ordering::Less => BigInt::new(other.negative, subtract(&other.digits, &self.digits)),
+      _ => BigInt::new(self.negative, subtract(&self.digits, &other.digits)),
+    }
+  }
+}
+
+impl Sub for &BigInt {
+  type Output = BigInt;
+
+  fn sub(self, other: &BigInt) -> BigInt {
+    self + &-other
+  }
+}
+
+impl Mul for &BigInt {
+  type Output = BigInt;
+
+  fn mul(self, other: &BigInt) -> BigInt {
+    BigInt::new(self.negative != other.negative, multiply(&self.digits, &other.digits))
+  }
+}
+
+// Operations on magnitudes, least significant digit first
+
+fn compare(a: &[u32], b: &[u32]) -> Ordering {
+  a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add(a: &[u32], b: &[u32]) -> Vec<u32> {
+  let mut sum = vec![];
+  let mut carry = 0;
+  for i in 0..a.len().max(b.len()) {
+    let digit = u64::from(*a.get(i).unwrap_or(&0)) + u64::from(*b.get(i).unwrap_or(&0)) + carry;
+    sum.push((digit % BASE) as u32);
+    carry = digit / BASE;
+  }
+  if carry > 0 {
+    sum.push(carry as u32);
+  }
+  sum
+}
+
+// The difference of a and a magnitude b that is not larger than a
+fn subtract(a: &[u32], b: &[u32]) -> Vec<u32> {
+  let mut difference = vec![];
+  let mut borrow = 0;
+  for (i, digit) in a.iter().enumerate() {
+    let subtrahend = i64::from(*b.get(i).unwrap_or(&0)) + borrow;
+    let mut digit = i64::from(*digit) - subtrahend;
+    borrow = 0;
+    if digit < 0 {
+      digit += BASE as i64;
+      borrow = 1;
+    }
+    difference.push(digit as u32);
+  }
+  while difference.last() == Some(&0) {
+    difference.pop();
+  }
+  difference
+}
+
+fn multiply(a: &[u32], b: &[u32]) -> Vec<u32> {
+  let mut product = vec![0u64; a.len() + b.len()];
+  for (i, x) in a.iter().enumerate() {
+    let mut carry = 0;
+    for (j, y) in b.iter().enumerate() {
+      let digit = product[i + j] + u64::from(*x) * u64::from(*y) + carry;
+      product[i + j] = digit % BASE;
+      carry = digit / BASE;
+    }
+    product[i + b.len()] += carry;
+  }
+  let mut product: Vec<u32> = product.into_iter().map(|digit| digit as u32).collect();
+  while product.last() == Some(&0) {
+    product.pop();
+  }
+  product
+}
+
+// Long division, one quotient digit at a time. Each digit is found by a binary search over the digit values.
+fn divide(a: &[u32], b: &[u32]) -> Vec<u32> {
+  let mut quotient = vec![0; a.len()];
+  let mut remainder: Vec<u32> = vec![];
+  for (i, digit) in a.iter().enumerate().rev() {
+    remainder.insert(0, *digit);
+    while remainder.last() == Some(&0) {
+      remainder.pop();
+    }
+    let (mut low, mut high) = (0, BASE as u32 - 1);
+    while low < high {
+      let middle = low + (high - low).div_ceil(2);
+      if compare(&multiply(b, &[middle]), &remainder) == Ordering::Greater {
+        high = middle - 1;
+      } else {
+        low = middle;
+      }
+    }
+    remainder = subtract(&remainder, &multiply(b, &[low]));
+    quotient[i] = low;
+  }
+  quotient
+}
@@ -0,0 +1,234 @@
+use crate::interpreter::Value;
+use crate::error::*;
+
+// A builtin takes the already evaluated call arguments and returns the result of the call.
+pub type Builtin = fn(Vec<Value>) -> Result<Value, AsaErrorKind>;
+
+// Builtin Table:
+// Returns the builtin with the given name. User defined functions take precedence over these.
+pub fn builtin(name: &str) -> Option<Builtin> {
+  match name {
+    "len" => Some(len),
+    "push" => Some(push),
+    "pop" => Some(pop),
+    "slice" => Some(slice),
+    "concat" => Some(concat),
+    "keys" => Some(keys),
+    "values" => Some(values),
+    "has" => Some(has),
+    _ => None,
+  }
+}
+
+// Builtins without side effects, the only functions that can be called in the initializer of a constant
+pub fn is_pure(name: &str) -> bool {
+  matches!(name, "len" | "slice" | "concat" | "keys" | "values" | "has")
+}
+
+fn check_arity(name: &str, args: &[Value], expected: usize) -> Result<(), AsaErrorKind> {
+  if args.len() != expected {
+    return Err(AsaErrorKind::Generic(format!(
+      "Function '{}' called with an incorrect number of arguments",
+      name
+    )));
+  }
+  Ok(())
+}
+
+// Converts a number argument into a position inside a collection of the given length. A big integer is converted
+// to a number first. The end of the collection is a valid position, so the same check works for slice bounds.
+fn position(index: &Value, length: usize) -> Result<usize, AsaErrorKind> {
+  match index {
+    Value::Number(n) if *n >= 0 && (*n as usize) <= length => Ok(*n as usize),
+    Value::Number(n) => Err(AsaErrorKind::IndexOutOfBounds { index: *n, length }),
+    Value::BigInt(n) => match n.to_i32() {
+      Some(n) => position(&Value::Number(n), length),
+      None => Err(AsaErrorKind::LargeIndexOutOfBounds { index: n.clone(), length }),
+    },
+    _ => Err(AsaErrorKind::Generic("Index must be a number".to_string())),
+  }
+}
+
+fn key(index: &Value) -> Result<&str, AsaErrorKind> {
+  match index {
+    Value::String(key) => Ok(key),
+    _ => Err(AsaErrorKind::Generic("Map key must be a string".to_string())),
+  }
+}
+
+// len(xs) - number of elements in a list or map, or characters in a string
+fn len(args: Vec<Value>) -> Result<Value, AsaErrorKind> {
+  check_arity("len", &args, 1)?;
+  match &args[0] {
+    Value::List(items) => Ok(Value::Number(items.borrow().len() as i32)),
+    Value::Map(entries) => Ok(Value::Number(entries.borrow().len() as i32)),
+    Value::String(s) => Ok(Value::Number(s.chars().count() as i32)),
+    _ => Err(AsaErrorKind::Generic("len expects a list, a map or a string".to_string())),
+  }
+}
+
+// push(xs, value) - appends the value to the list in place and returns the list
+fn push(args: Vec<Value>) -> Result<Value, AsaErrorKind> {
+  check_arity("push", &args, 2)?;
+  match &args[0] {
+    Value::List(items) => {
+      items.borrow_mut().push(args[1].clone());
+      Ok(args[0].clone())
+    }
+    _ => Err(AsaErrorKind::Generic("push expects a list".to_string())),
+  }
+}
+
+// pop(xs) - removes the last element of the list in place and returns it
+fn pop(args: Vec<Value>) -> Result<Value, AsaErrorKind> {
+  check_arity("pop", &args, 1)?;
+  match &args[0] {
+    Value::List(items) => items.borrow_mut().pop().ok_or(AsaErrorKind::EmptyList),
+    _ => Err(AsaErrorKind::Generic("pop expects a list".to_string())),
+  }
+}
+
+// slice(xs, start, end) - new list with the elements from start up to, but not including, end
+fn slice(args: Vec<Value>) -> Result<Value, AsaErrorKind> {
+  check_arity("slice", &args, 3)?;
+  match &args[0] {
+    Value::List(items) => {
+      let items = items.borrow();
+      let start = position(&args[1], items.len())?;
+      let end = position(&args[2], items.len())?;
+      if start > end {
+        return Err(AsaErrorKind::InvalidSlice { start: start as i32, end: end as i32 });
+      }
+      Ok(Value::list(items[start..end].to_vec()))
+    }
+    _ => Err(AsaErrorKind::Generic("slice expects a list".to_string())),
+  }
+}
+
+// concat(xs, ys) - new list with the elements of xs followed by the elements of ys
+fn concat(args: Vec<Value>) -> Result<Value, AsaErrorKind> {
+  check_arity("concat", &args, 2)?;
+  match (&args[0], &args[1]) {
+    (Value::List(left), Value::List(right)) => {
+      let mut items = left.borrow().clone();
+      items.extend(right.borrow().iter().cloned());
+      Ok(Value::list(items))
+    }
+    _ => Err(AsaErrorKind::Generic("concat expects two lists".to_string())),
+  }
+}
+
+// keys(m) - list of the keys of a map, in insertion order
+fn keys(args: Vec<Value>) -> Result<Value, AsaErrorKind> {
+  check_arity("keys", &args, 1)?;
+  match &args[0] {
+    Value::Map(entries) => Ok(Value::list(
+      entries.borrow().iter().map(|(key, _)| Value::String(key.clone())).collect()
+    )),
+    _ => Err(AsaErrorKind::Generic("keys expects a map".to_string())),
+  }
+}
+
+// values(m) - list of the values of a map, in insertion order
+fn values(args: Vec<Value>) -> Result<Value, AsaErrorKind> {
+  check_arity("values", &args, 1)?;
+  match &args[0] {
+    Value::Map(entries) => Ok(Value::list(
+      entries.borrow().iter().map(|(_, value)| value.clone()).collect()
+    )),
+    _ => Err(AsaErrorKind::Generic("values expects a map".to_string())),
+  }
+}
+
+// has(m, key) - whether the map contains the key
+fn has(args: Vec<Value>) -> Result<Value, AsaErrorKind> {
+  check_arity("has", &args, 2)?;
+  match &args[0] {
+    Value::Map(entries) => {
+      let key = key(&args[1])?;
+      Ok(Value::Bool(entries.borrow().iter().any(|(k, _)| k == key)))
+    }
+    _ => Err(AsaErrorKind::Generic("has expects a map".to_string())),
+  }
+}
+
+// Reads the element at the given index of a list, or the value of the given key of a map
+pub fn get_index(collection: &Value, index: &Value) -> Result<Value, AsaErrorKind> {
+  match collection {
+    Value::List(items) => {
+      let items = items.borrow();
+      match position(index, items.len())? {
+        i if i < items.len() => Ok(items[i].clone()),
+        i => Err(AsaErrorKind::IndexOutOfBounds { index: i as i32, length: items.len() }),
+      }
+    }
+    Value::Map(entries) => {
+      let key = key(index)?;
+      entries.borrow().iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.clone())
+        .ok_or(AsaErrorKind::KeyNotFound(key.to_string()))
+    }
+    _ => Err(AsaErrorKind::Generic("Only lists and maps can be indexed".to_string())),
+  }
+}
+
+// Replaces the element at the given index of a list. For maps, the value of the key is replaced,
+// or a new entry is added at the end if the key is not in the map yet.
+pub fn set_index(collection: &Value, index: &Value, value: Value) -> Result<(), AsaErrorKind> {
+  match collection {
+    Value::List(items) => {
+      let mut items = items.borrow_mut();
+      let length = items.len();
+      match position(index, length)? {
+        i if i < length => {
+          items[i] = value;
+          Ok(())
+        }
+        i => Err(AsaErrorKind::IndexOutOfBounds { index: i as i32, length }),
+      }
+    }
+    Value::Map(entries) => {
+      let key = key(index)?;
+      let mut entries = entries.borrow_mut();
+      match entries.iter_mut().find(|(k, _)| k == key) {
+        Some(entry) => entry.1 = value,
+        None => entries.push((key.to_string(), value)),
+      }
+      Ok(())
+    }
+    _ => Err(AsaErrorKind::Generic("Only lists and maps can be indexed".to_string())),
+  }
+}
+
+// Reads a field of a struct instance
+pub fn get_field(instance: &Value, field: &str) -> Result<Value, AsaErrorKind> {
+  match instance {
+    Value::Struct(type_name, fields) => fields.borrow().iter()
+      .find(|(f, _)| f == field)
+      .map(|(_, value)| value.clone())
+      .ok_or(AsaErrorKind::UnknownField { type_name: type_name.clone(), field: field.to_string() }),
+    // The members of an imported module are read like fields
+    Value::Module(module) => module.members.get(field)
+      .cloned()
+      .ok_or(AsaErrorKind::UnknownField { type_name: module.path.clone(), field: field.to_string() }),
+    _ => Err(AsaErrorKind::Generic(format!("Cannot access field '{}' of a value that is not a struct", field))),
+  }
+}
+
+// Replaces the value of a field of a struct instance. Fields cannot be added after construction.
+pub fn set_field(instance: &Value, field: &str, value: Value) -> Result<(), AsaErrorKind> {
+  match instance {
+    Value::Struct(type_name, fields) => {
+      let mut fields = fields.borrow_mut();
+      match fields.iter_mut().find(|(f, _)| f == field) {
+        Some(entry) => {
+          entry.1 = value;
+          Ok(())
+        }
+        None => Err(AsaErrorKind::UnknownField { type_name: type_name.clone(), field: field.to_string() }),
+      }
+    }
+    _ => Err(AsaErrorKind::Generic(format!("Cannot access field '{}' of a value that is not a struct", field))),
+  }
+}
@@ -0,0 +1,180 @@
+// Checks run on the syntax tree before the program is executed.
+
+use crate::parser::Node;
+use crate::error::*;
+use crate::builtins::is_pure;
+use std::collections::HashSet;
+
+// Checks that named arguments follow the positional ones. Calls of undefined functions are reported by the resolver.
+// Top level constants cannot be redefined at the top level or be the target of an assignment, unless a local
+// variable with the same name shadows them. Constants are shallow: only the name is protected, a list, map or
+// struct held by a constant can still change through another variable holding the same value, e.g. by push.
+pub fn check_calls(program: &Node) -> Result<(), AsaErrorKind> {
+  let mut constants = HashSet::new();
+  for item in program.children() {
+    if let Node::ConstDefine { children } = item {
+      let name = identifier(&children[0]).unwrap_or_default();
+      if !constants.insert(name.clone()) {
+        return Err(AsaErrorKind::ConstantRedefined(name));
+      }
+    }
+  }
+  for item in program.children() {
+    let name = match item {
+      Node::VariableDefine { children } => identifier(&children[0]),
+      Node::FunctionDefine { name, .. } => Some(String::from_utf8_lossy(name).to_string()),
+      _ => None,
+    };
+    if let Some(name) = name.filter(|name| constants.contains(name)) {
+      return Err(AsaErrorKind::ConstantRedefined(name));
+    }
+  }
+  let mut checker = CallChecker { scopes: vec![], constants };
+  checker.check_scope(program.children(), HashSet::new())
+}
+
+// Checks that the initializer of a constant is a constant expression: literals, operators, lists, maps, string
+// interpolations, calls to pure builtins and other constants. Returns the constants the initializer refers to.
+pub fn constant_dependencies(name: &str, initializer: &Node, is_constant: &dyn Fn(&str) -> bool) -> Result<Vec<String>, AsaErrorKind> {
+  let mut dependencies = vec![];
+  let mut nodes = vec![initializer];
+  while let Some(node) = nodes.pop() {
+    match node {
+      Node::Identifier { value, .. } | Node::Variable { name: value, .. } if is_constant(&String::from_utf8_lossy(value)) => {
+        dependencies.push(String::from_utf8_lossy(value).to_string());
+      }
+      Node::FunctionCall { name: callee, .. } if !is_pure(&String::from_utf8_lossy(callee)) => {
+        return Err(AsaErrorKind::NotConstant(name.to_string()));
+      }
+      Node::Number { .. } | Node::BigInt { .. } | Node::LargeNumber { .. } | Node::String { .. } | Node::Bool { .. } | Node::Expression { .. } |
+      Node::MathExpression { .. } | Node::ConditionalExpression { .. } | Node::Interpolation { .. } |
+      Node::List { .. } | Node::Map { .. } | Node::KeyValue { .. } | Node::Index { .. } |
+      Node::FunctionCall { .. } | Node::FunctionArguments { .. } | Node::Spread { .. } => {},
+      _ => return Err(AsaErrorKind::NotConstant(name.to_string())),
+    }
+    nodes.extend(node.children());
+  }
+  Ok(dependencies)
+}
+
+struct CallChecker {
+  // Names bound in the scopes around the current node, innermost last. A local variable with the name of
+  // a constant shadows it.
+  scopes: Vec<HashSet<String>>,
+  // Top level constants
+  constants: HashSet<String>,
+}
+
+impl CallChecker {
+  // Checks the statements of a program, block or function body in a new scope. Variables and nested functions
+  // are visible in the whole scope, also before their definition.
+  fn check_scope(&mut self, statements: &[Node], mut names: HashSet<String>) -> Result<(), AsaErrorKind> {
+    for statement in statements {
+      match statement {
+        Node::VariableDefine { children } |
+        Node::ConstDefine { children } => names.extend(identifier(&children[0])),
+        Node::FunctionDefine { name, .. } => { names.insert(String::from_utf8_lossy(name).to_string()); },
+        _ => {},
+      }
+    }
+    self.scopes.push(names);
+    let result = statements.iter().try_for_each(|statement| self.check(statement));
+    self.scopes.pop();
+    result
+  }
+
+  fn check(&mut self, node: &Node) -> Result<(), AsaErrorKind> {
+    match node {
+      // The parameters are bound in the body and the default values, a named function can also call itself
+      Node::FunctionDefine { name, children } => {
+        let mut names = parameters(&children[0]);
+        names.insert(String::from_utf8_lossy(name).to_string());
+        self.check_function(children, names)
+      }
+      Node::Lambda { children } => self.check_function(children, parameters(&children[0])),
+      // Named arguments follow the positional ones
+      Node::FunctionArguments { children } => {
+        let mut named = None;
+        for child in children {
+          match child {
+            Node::NamedArgument { span, .. } => named = Some(*span),
+            _ => if let Some(span) = named {
+              return Err(AsaErrorKind::PositionalAfterNamed(span));
+            },
+          }
+          self.check(child)?;
+        }
+        Ok(())
+      }
+      Node::Block { children } => self.check_scope(children, HashSet::new()),
+      // The error is bound in the handler
+      Node::Try { children } => {
+        self.check(&children[0])?;
+        self.check_scope(children[2].children(), identifier(&children[1]).into_iter().collect())?;
+        children[3..].iter().try_for_each(|child| self.check(child))
+      }
+      // The names bound by the pattern are visible in the guard and the body of the arm
+      Node::MatchArm { children } => {
+        let mut names = HashSet::new();
+        pattern_names(&children[0], &mut names);
+        self.check_scope(&children[1..], names)
+      }
+      // Only the value is checked, the name was bound when entering the scope
+      Node::VariableDefine { children } |
+      Node::ConstDefine { children } => self.check(&children[1]),
+      // The collection or struct that is updated must not be a constant
+      Node::Assignment { children } => {
+        if let Some(name) = assignment_root(&children[0]) {
+          let shadowed = self.scopes.iter().skip(1).any(|scope| scope.contains(&name));
+          if self.constants.contains(&name) && !shadowed {
+            return Err(AsaErrorKind::AssignmentToConstant(name));
+          }
+        }
+        children.iter().try_for_each(|child| self.check(child))
+      }
+      _ => node.children().iter().try_for_each(|child| self.check(child)),
+    }
+  }
+
+  fn check_function(&mut self, children: &[Node], names: HashSet<String>) -> Result<(), AsaErrorKind> {
+    self.scopes.push(names.clone());
+    let defaults = children[0].children().iter()
+      .flat_map(|parameter| parameter.children().iter().skip(1))
+      .try_for_each(|default| self.check(default));
+    self.scopes.pop();
+    defaults?;
+    self.check_scope(children[1].children(), names)
+  }
+}
+
+fn identifier(node: &Node) -> Option<String> {
+  match node {
+    Node::Identifier { value, .. } => Some(String::from_utf8_lossy(value).to_string()),
+    _ => None,
+  }
+}
+
+// Names of the parameters of a function. Every parameter is an Expression node holding the identifier.
+fn parameters(arguments: &Node) -> HashSet<String> {
+  arguments.children().iter()
+    .filter_map(|argument| argument.children().first().and_then(identifier))
+    .collect()
+}
+
+// The variable an assignment target is rooted in, e.g. xs for xs[0].y = 1
+fn assignment_root(target: &Node) -> Option<String> {
+  match target {
+    Node::Identifier { value, .. } => Some(String::from_utf8_lossy(value).to_string()),
+    Node::Index { children } | Node::FieldAccess { children, .. } | Node::Expression { children } => {
+      children.first().and_then(assignment_root)
+    }
+    _ => None,
+  }
+}
+
+fn pattern_names(pattern: &Node, names: &mut HashSet<String>) {
+  match pattern {
+    Node::Identifier { value, .. } => { names.insert(String::from_utf8_lossy(value).to_string()); },
+    _ => pattern.children().iter().for_each(|child| pattern_names(child, names)),
+  }
+}
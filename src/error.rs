@@ -1,9 +1,149 @@
+use crate::lexer::Span;
+use crate::interpreter::Value;
+use crate::bigint::BigInt;
+
 #[derive(Debug,PartialEq)]
 pub enum AsaErrorKind {
   UndefinedFunction,
-  VariableNotDefined(String),
+  UndefinedFunctionCall { name: String, span: Span },
+  // A variable that is not defined, or read before its definition ran, at the span of its name
+  VariableNotDefined { name: String, span: Span },
   DivisionByZero,
-  NumberOverflow,
-  NumberUnderflow,
+  // A number result above the largest or below the smallest number, at the span of the operator
+  NumberOverflow(Span),
+  NumberUnderflow(Span),
+  IndexOutOfBounds { index: i32, length: usize },
+  // A big integer index that does not even fit in a number, seen by a catch block as IndexOutOfBounds
+  LargeIndexOutOfBounds { index: BigInt, length: usize },
+  // pop of a list without elements
+  EmptyList,
+  // A slice whose start is after its end
+  InvalidSlice { start: i32, end: i32 },
+  KeyNotFound(String),
+  UndefinedType(String),
+  // A type annotation names a type that is not defined
+  UndefinedTypeAnnotation { name: String, span: Span },
+  UnknownField { type_name: String, field: String },
+  MissingField { type_name: String, field: String },
+  NonExhaustiveMatch(Span),
+  ModuleNotFound(String),
+  ImportCycle(Vec<String>),
+  TypeMismatch { expected: String, found: String, span: Span },
+  // A type parameter inferred as one type from an earlier argument is used with another type
+  TypeParameterMismatch { parameter: String, expected: String, found: String, span: Span },
+  // All type errors found in a program
+  TypeErrors(Vec<AsaErrorKind>),
+  // A named argument that is not a parameter of the called function
+  UnknownArgument { function: String, argument: String },
+  // A parameter that is passed more than once, by position or by name
+  DuplicateArgument { function: String, argument: String },
+  // A named argument for the rest parameter, which only takes positional arguments
+  RestArgumentByName { function: String, argument: String },
+  // A parameter without a default value that is not passed
+  MissingArgument { function: String, argument: String },
+  // A positional argument after the named argument at the span
+  PositionalAfterNamed(Span),
+  // The initializer of the constant is not a constant expression
+  NotConstant(String),
+  // Constants whose initializers depend on each other, the first constant is repeated at the end
+  ConstantCycle(Vec<String>),
+  // A top level constant, variable or function with the name of a constant
+  ConstantRedefined(String),
+  // A constant, or a collection or struct held by it, is the target of an assignment
+  AssignmentToConstant(String),
+  // A value thrown by a throw statement and not caught
+  Thrown(Value),
+  // A call of the function would exceed the maximum call depth, or a node in the function would be nested deeper
+  // than the maximum nesting. The chain holds the running functions, outermost first, followed by the function
+  // if it is called.
+  StackOverflow { function: String, chain: Vec<String> },
   Generic(String),  
+}
+
+impl AsaErrorKind {
+  // The kind of a runtime error as seen by a catch block
+  pub fn kind(&self) -> &'static str {
+    match self {
+      AsaErrorKind::UndefinedFunction => "UndefinedFunction",
+      AsaErrorKind::UndefinedFunctionCall { .. } => "UndefinedFunctionCall",
+      AsaErrorKind::VariableNotDefined { .. } => "VariableNotDefined",
+      AsaErrorKind::DivisionByZero => "DivisionByZero",
+      AsaErrorKind::NumberOverflow(_) => "NumberOverflow",
+      AsaErrorKind::NumberUnderflow(_) => "NumberUnderflow",
+      AsaErrorKind::IndexOutOfBounds { .. } |
+      AsaErrorKind::LargeIndexOutOfBounds { .. } => "IndexOutOfBounds",
+      AsaErrorKind::EmptyList => "EmptyList",
+      AsaErrorKind::InvalidSlice { .. } => "InvalidSlice",
+      AsaErrorKind::KeyNotFound(_) => "KeyNotFound",
+      AsaErrorKind::UndefinedType(_) => "UndefinedType",
+      AsaErrorKind::UndefinedTypeAnnotation { .. } => "UndefinedTypeAnnotation",
+      AsaErrorKind::UnknownField { .. } => "UnknownField",
+      AsaErrorKind::MissingField { .. } => "MissingField",
+      AsaErrorKind::NonExhaustiveMatch(_) => "NonExhaustiveMatch",
+      AsaErrorKind::ModuleNotFound(_) => "ModuleNotFound",
+      AsaErrorKind::ImportCycle(_) => "ImportCycle",
+      AsaErrorKind::TypeMismatch { .. } => "TypeMismatch",
+      AsaErrorKind::TypeParameterMismatch { .. } => "TypeParameterMismatch",
+      AsaErrorKind::TypeErrors(_) => "TypeErrors",
+      AsaErrorKind::UnknownArgument { .. } => "UnknownArgument",
+      AsaErrorKind::DuplicateArgument { .. } => "DuplicateArgument",
+      AsaErrorKind::RestArgumentByName { .. } => "RestArgumentByName",
+      AsaErrorKind::MissingArgument { .. } => "MissingArgument",
+      AsaErrorKind::PositionalAfterNamed(_) => "PositionalAfterNamed",
+      AsaErrorKind::NotConstant(_) => "NotConstant",
+      AsaErrorKind::ConstantCycle(_) => "ConstantCycle",
+      AsaErrorKind::ConstantRedefined(_) => "ConstantRedefined",
+      AsaErrorKind::AssignmentToConstant(_) => "AssignmentToConstant",
+      AsaErrorKind::Thrown(_) => "Thrown",
+      AsaErrorKind::StackOverflow { .. } => "StackOverflow",
+      AsaErrorKind::Generic(_) => "Error",
+    }
+  }
+}
+
+impl std::fmt::Display for AsaErrorKind {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      AsaErrorKind::UndefinedFunction => write!(f, "undefined function or variable"),
+      AsaErrorKind::UndefinedFunctionCall { name, .. } => write!(f, "call to undefined function '{}'", name),
+      AsaErrorKind::VariableNotDefined { name, .. } => write!(f, "variable '{}' is not defined", name),
+      AsaErrorKind::DivisionByZero => write!(f, "division by zero"),
+      AsaErrorKind::NumberOverflow(_) => write!(f, "number overflow"),
+      AsaErrorKind::NumberUnderflow(_) => write!(f, "number underflow"),
+      AsaErrorKind::IndexOutOfBounds { index, length } => write!(f, "index {} out of bounds for length {}", index, length),
+      AsaErrorKind::LargeIndexOutOfBounds { index, length } => write!(f, "index {} out of bounds for length {}", index, length),
+      AsaErrorKind::EmptyList => write!(f, "list is empty"),
+      AsaErrorKind::InvalidSlice { start, end } => write!(f, "slice start {} is after its end {}", start, end),
+      AsaErrorKind::KeyNotFound(key) => write!(f, "key '{}' not found", key),
+      AsaErrorKind::UndefinedType(name) => write!(f, "type '{}' is not defined", name),
+      AsaErrorKind::UndefinedTypeAnnotation { name, .. } => write!(f, "type '{}' is not defined", name),
+      AsaErrorKind::UnknownField { type_name, field } => write!(f, "'{}' has no field '{}'", type_name, field),
+      AsaErrorKind::MissingField { type_name, field } => write!(f, "field '{}' of '{}' is not initialized", field, type_name),
+      AsaErrorKind::NonExhaustiveMatch(_) => write!(f, "no match arm matches the value"),
+      AsaErrorKind::ModuleNotFound(path) => write!(f, "module '{}' not found", path),
+      AsaErrorKind::ImportCycle(cycle) => write!(f, "import cycle: {}", cycle.join(" -> ")),
+      AsaErrorKind::TypeMismatch { expected, found, .. } => write!(f, "expected {}, found {}", expected, found),
+      AsaErrorKind::TypeParameterMismatch { parameter, expected, found, .. } => {
+        write!(f, "type parameter {} is {}, found {}", parameter, expected, found)
+      }
+      AsaErrorKind::TypeErrors(errors) => {
+        let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+        write!(f, "{}", errors.join("; "))
+      }
+      AsaErrorKind::UnknownArgument { function, argument } => write!(f, "'{}' has no parameter '{}'", function, argument),
+      AsaErrorKind::DuplicateArgument { function, argument } => write!(f, "parameter '{}' of '{}' passed more than once", argument, function),
+      AsaErrorKind::RestArgumentByName { function, argument } => write!(f, "cannot pass rest parameter '{}' of '{}' by name", argument, function),
+      AsaErrorKind::MissingArgument { function, argument } => write!(f, "missing argument '{}' of '{}'", argument, function),
+      AsaErrorKind::PositionalAfterNamed(_) => write!(f, "positional argument after named argument"),
+      AsaErrorKind::NotConstant(name) => write!(f, "initializer of constant '{}' is not constant", name),
+      AsaErrorKind::ConstantCycle(cycle) => write!(f, "constant cycle: {}", cycle.join(" -> ")),
+      AsaErrorKind::ConstantRedefined(name) => write!(f, "constant '{}' redefined", name),
+      AsaErrorKind::AssignmentToConstant(name) => write!(f, "cannot assign to constant '{}'", name),
+      AsaErrorKind::Thrown(value) => write!(f, "uncaught {}", value),
+      AsaErrorKind::StackOverflow { function, chain } => {
+        write!(f, "maximum depth exceeded in '{}': {}", function, chain.join(" -> "))
+      }
+      AsaErrorKind::Generic(message) => write!(f, "{}", message),
+    }
+  }
 }
\ No newline at end of file
@@ -1,379 +1,1538 @@
 use crate::parser::Node;
-use std::collections::HashMap;
+use crate::lexer::Span;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::cell::RefCell;
 use crate::error::*;
+use crate::builtins::*;
+use crate::checker::*;
+use crate::modules::*;
+use crate::typechecker::*;
+use crate::resolver::*;
+use crate::bigint::BigInt;
+use crate::lexer::lex;
+use crate::parser::program;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Clone)]
 pub enum Value {
+  // The value of nil, of statements and of blocks and programs without a value
+  Nil,
   String(String),
   Number(i32),
+  // An integer of any size. Operations with a big integer operand have a big integer result,
+  // numbers and big integers with the same value are equal.
+  BigInt(BigInt),
   Bool(bool),
+  // Lists are shared: copies of a list value refer to the same elements,
+  // so push/pop and index assignment are visible through every copy.
+  List(Rc<RefCell<Vec<Value>>>),
+  // Maps are shared like lists. Entries are kept in insertion order.
+  Map(Rc<RefCell<Vec<(String, Value)>>>),
+  // Struct instances are shared like lists. The fields are kept in declaration order,
+  // so two instances are equal when they have the same type and equal field values.
+  Struct(String, Rc<RefCell<Vec<(String, Value)>>>),
+  // Enum type name, variant name and the values of the variant fields
+  Variant(String, String, Vec<Value>),
+  Function(Rc<Function>),
+  // The namespace of an imported module
+  Module(Rc<Module>),
 }
 
-type Frame = HashMap<String, Value>;
-type Arguments = Node;
-type Statements = Node;
+// A function value: the parameters and body of a function definition or lambda, and the values of the variables
+// it captured where it was created, see Binding::Captured. Top level functions capture nothing.
+#[derive(Debug, PartialEq)]
+pub struct Function {
+  // Named functions can refer to themselves by name, lambdas have no name
+  pub name: Option<String>,
+  pub arguments: Node,
+  pub statements: Node,
+  pub environment: RefCell<Vec<Value>>,
+  // Where the captured variables are in the frame the function was created in. A function that captures a function
+  // of its block defined after it gets the value of that function when the definition runs, see complete_functions.
+  pub captured: Vec<Binding>,
+  // Path of the module the function was defined in. The function runs with the definitions and globals of that module.
+  pub module: String,
+}
+
+impl Value {
+  pub fn list(items: Vec<Value>) -> Value {
+    Value::List(Rc::new(RefCell::new(items)))
+  }
+
+  pub fn map(entries: Vec<(String, Value)>) -> Value {
+    Value::Map(Rc::new(RefCell::new(entries)))
+  }
+
+  pub fn new_struct(type_name: &str, fields: Vec<(String, Value)>) -> Value {
+    Value::Struct(type_name.to_string(), Rc::new(RefCell::new(fields)))
+  }
+}
+
+// Structural equality of values. Lists, maps and structs can contain themselves, so the pairs of containers that are
+// being compared are remembered. A pair that comes up again is taken as equal, which ends the comparison of cyclic
+// values: two values are only unequal if some element reachable in both differs. Functions and modules are equal
+// when they are the same value. A number and a big integer are never equal here, see equal for the language's ==.
+impl PartialEq for Value {
+  fn eq(&self, other: &Value) -> bool {
+    equal_in(self, other, false, &mut HashSet::new())
+  }
+}
+
+// The addresses of two containers that are compared
+type Pair = (*const (), *const ());
+
+// With by_value, numbers and big integers with the same value are equal, also inside containers
+fn equal_in(left: &Value, right: &Value, by_value: bool, compared: &mut HashSet<Pair>) -> bool {
+  match (left, right) {
+    (Value::Nil, Value::Nil) => true,
+    (Value::String(l), Value::String(r)) => l == r,
+    (Value::Number(l), Value::Number(r)) => l == r,
+    (Value::BigInt(l), Value::BigInt(r)) => l == r,
+    (Value::Number(n), Value::BigInt(big)) | (Value::BigInt(big), Value::Number(n)) => by_value && BigInt::from(*n) == *big,
+    (Value::Bool(l), Value::Bool(r)) => l == r,
+    (Value::List(l), Value::List(r)) => {
+      compared_before(Rc::as_ptr(l).cast(), Rc::as_ptr(r).cast(), compared)
+        || elements_equal(&l.borrow(), &r.borrow(), by_value, compared)
+    }
+    (Value::Map(l), Value::Map(r)) => {
+      compared_before(Rc::as_ptr(l).cast(), Rc::as_ptr(r).cast(), compared)
+        || entries_equal(&l.borrow(), &r.borrow(), by_value, compared)
+    }
+    (Value::Struct(l_type, l), Value::Struct(r_type, r)) => l_type == r_type && (
+      compared_before(Rc::as_ptr(l).cast(), Rc::as_ptr(r).cast(), compared)
+        || entries_equal(&l.borrow(), &r.borrow(), by_value, compared)
+    ),
+    (Value::Variant(l_enum, l_variant, l), Value::Variant(r_enum, r_variant, r)) => {
+      l_enum == r_enum && l_variant == r_variant && elements_equal(l, r, by_value, compared)
+    }
+    (Value::Function(l), Value::Function(r)) => Rc::ptr_eq(l, r),
+    (Value::Module(l), Value::Module(r)) => Rc::ptr_eq(l, r),
+    _ => false,
+  }
+}
+
+// Whether two containers are the same or their comparison has already started. Otherwise the pair is remembered.
+fn compared_before(left: *const (), right: *const (), compared: &mut HashSet<Pair>) -> bool {
+  left == right || !compared.insert((left, right))
+}
+
+fn elements_equal(left: &[Value], right: &[Value], by_value: bool, compared: &mut HashSet<Pair>) -> bool {
+  left.len() == right.len() && left.iter().zip(right).all(|(l, r)| equal_in(l, r, by_value, compared))
+}
+
+// Entries of maps and fields of structs, which are equal in the same order
+fn entries_equal(left: &[(String, Value)], right: &[(String, Value)], by_value: bool, compared: &mut HashSet<Pair>) -> bool {
+  left.len() == right.len() && left.iter().zip(right)
+    .all(|((l_key, l), (r_key, r))| l_key == r_key && equal_in(l, r, by_value, compared))
+}
+
+// The display form of a value, used by string interpolation. Strings are shown as they are,
+// except inside collections and structs where they are quoted.
+impl std::fmt::Display for Value {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(f, "{}", display(self, false, &mut vec![]))
+  }
+}
+
+// The debug form of a value shows its variant like a derived Debug would. A container inside itself is shown as ...
+// like in the display form, and functions only by their name, since their environment can hold the function itself.
+impl std::fmt::Debug for Value {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    Debugged { value: self, open: &RefCell::new(vec![]) }.fmt(f)
+  }
+}
+
+// A value with the containers it is inside of, see display
+struct Debugged<'a> {
+  value: &'a Value,
+  open: &'a RefCell<Vec<*const ()>>,
+}
+
+impl std::fmt::Debug for Debugged<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    let debugged = |value| Debugged { value, open: self.open };
+    let container = container(self.value);
+    if let Some(container) = container {
+      if self.open.borrow().contains(&container) {
+        return f.write_str("...");
+      }
+      self.open.borrow_mut().push(container);
+    }
+    let result = match self.value {
+      Value::Nil => f.write_str("Nil"),
+      Value::String(s) => f.debug_tuple("String").field(s).finish(),
+      Value::Number(n) => f.debug_tuple("Number").field(n).finish(),
+      Value::BigInt(n) => f.debug_tuple("BigInt").field(n).finish(),
+      Value::Bool(b) => f.debug_tuple("Bool").field(b).finish(),
+      Value::List(items) => {
+        let items = items.borrow();
+        let items: Vec<Debugged> = items.iter().map(debugged).collect();
+        f.debug_tuple("List").field(&items).finish()
+      }
+      Value::Map(entries) => {
+        let entries = entries.borrow();
+        let entries: Vec<(&String, Debugged)> = entries.iter().map(|(key, value)| (key, debugged(value))).collect();
+        f.debug_tuple("Map").field(&entries).finish()
+      }
+      Value::Struct(type_name, fields) => {
+        let fields = fields.borrow();
+        let fields: Vec<(&String, Debugged)> = fields.iter().map(|(field, value)| (field, debugged(value))).collect();
+        f.debug_tuple("Struct").field(type_name).field(&fields).finish()
+      }
+      Value::Variant(enum_name, variant, values) => {
+        let values: Vec<Debugged> = values.iter().map(debugged).collect();
+        f.debug_tuple("Variant").field(enum_name).field(variant).field(&values).finish()
+      }
+      Value::Function(function) => f.debug_tuple("Function").field(&function.name).finish(),
+      Value::Module(module) => f.debug_tuple("Module").field(&module.path).finish(),
+    };
+    if container.is_some() {
+      self.open.borrow_mut().pop();
+    }
+    result
+  }
+}
+
+// The address of a list, map or struct, which can be inside itself
+fn container(value: &Value) -> Option<*const ()> {
+  match value {
+    Value::List(items) => Some(Rc::as_ptr(items).cast()),
+    Value::Map(entries) => Some(Rc::as_ptr(entries).cast()),
+    Value::Struct(_, fields) => Some(Rc::as_ptr(fields).cast()),
+    _ => None,
+  }
+}
+
+// The open containers are the lists, maps and structs the value is inside of. A container inside itself is shown as ...
+fn display(value: &Value, quoted: bool, open: &mut Vec<*const ()>) -> String {
+  let container = container(value);
+  if let Some(container) = container {
+    if open.contains(&container) {
+      return "...".to_string();
+    }
+    open.push(container);
+  }
+  let shown = match value {
+    Value::Nil => "nil".to_string(),
+    Value::String(s) if quoted => format!("\"{}\"", s),
+    Value::String(s) => s.clone(),
+    Value::Number(n) => n.to_string(),
+    Value::BigInt(n) => n.to_string(),
+    Value::Bool(b) => b.to_string(),
+    Value::List(items) => {
+      let items: Vec<String> = items.borrow().iter().map(|item| display(item, true, open)).collect();
+      format!("[{}]", items.join(", "))
+    }
+    Value::Map(entries) => {
+      let entries: Vec<String> = entries.borrow().iter()
+        .map(|(key, value)| format!("\"{}\": {}", key, display(value, true, open)))
+        .collect();
+      format!("{{{}}}", entries.join(", "))
+    }
+    Value::Struct(type_name, fields) => {
+      let fields: Vec<String> = fields.borrow().iter()
+        .map(|(field, value)| format!("{}: {}", field, display(value, true, open)))
+        .collect();
+      format!("{} {{ {} }}", type_name, fields.join(", "))
+    }
+    Value::Variant(_, variant, values) if values.is_empty() => variant.clone(),
+    Value::Variant(_, variant, values) => {
+      let values: Vec<String> = values.iter().map(|value| display(value, true, open)).collect();
+      format!("{}({})", variant, values.join(", "))
+    }
+    Value::Function(function) => format!("<fn {}>", function.name.as_deref().unwrap_or("lambda")),
+    Value::Module(module) => format!("<module {}>", module.path),
+  };
+  if container.is_some() {
+    open.pop();
+  }
+  shown
+}
+
+// The variables of a running function by their slots, see Binding::Local. The function is kept for its environment.
+#[derive(Debug, Default)]
+struct Frame {
+  slots: Vec<Value>,
+  function: Option<Rc<Function>>,
+}
+
+// The evaluated arguments of a call
+#[derive(Debug, Default)]
+struct Arguments {
+  positional: Vec<Value>,
+  named: Vec<(String, Value)>,
+}
+
+impl Arguments {
+  // The arguments of enum variants and builtins, which have no parameter names
+  fn positional(self, callee: &str) -> Result<Vec<Value>, AsaErrorKind> {
+    match self.named.into_iter().next() {
+      Some((argument, _)) => Err(AsaErrorKind::UnknownArgument { function: callee.to_string(), argument }),
+      None => Ok(self.positional),
+    }
+  }
+}
+
+// The name of a parameter, the first child of a parameter node
+fn parameter_name(children: &[Node]) -> Result<String, AsaErrorKind> {
+  match children.first() {
+    Some(Node::Identifier { value, .. }) => Ok(String::from_utf8_lossy(value).to_string()),
+    _ => Err(AsaErrorKind::Generic("Expected identifier inside expression".into())),
+  }
+}
+
+// The binding of a variable node, the resolver replaces the names that are defined by Variable nodes
+fn binding(node: &Node) -> Result<Binding, AsaErrorKind> {
+  match node {
+    Node::Variable { binding, .. } => Ok(*binding),
+    _ => Err(AsaErrorKind::Generic("Expected a resolved variable".to_string())),
+  }
+}
+
+// The value of a number or a big integer as a big integer
+fn integer(value: &Value) -> Option<BigInt> {
+  match value {
+    Value::Number(n) => Some(BigInt::from(*n)),
+    Value::BigInt(n) => Some(n.clone()),
+    _ => None,
+  }
+}
+
+// Equality of values as seen by == and by patterns. Numbers and big integers are compared by value, also as
+// elements of containers, other values structurally.
+fn equal(left: &Value, right: &Value) -> bool {
+  equal_in(left, right, true, &mut HashSet::new())
+}
+
+// Operations with a big integer operand, which are exact
+fn big_math(op1: &BigInt, op2: &BigInt, name: &[u8]) -> Result<Value, AsaErrorKind> {
+  let result = match name {
+    b"add" => op1 + op2,
+    b"sub" => op1 - op2,
+    b"mul" => op1 * op2,
+    b"div" => op1.checked_div(op2).ok_or(AsaErrorKind::DivisionByZero)?,
+    _ => return Err(AsaErrorKind::Generic("MathOperationError".into())),
+  };
+  Ok(Value::BigInt(result))
+}
+
+// The value a catch block receives for an error
+fn error_value(error: AsaErrorKind) -> Value {
+  match error {
+    AsaErrorKind::Thrown(value) => value,
+    error => Value::new_struct("Error", vec![
+      ("kind".to_string(), Value::String(error.kind().to_string())),
+      ("message".to_string(), Value::String(error.to_string())),
+    ]),
+  }
+}
+
+// The outcome of running a node that did not fail. A return unwinds the blocks and expressions around it
+// up to the call of the function it returns from, see call_function.
+#[derive(Debug)]
+enum Flow<T = Value> {
+  Normal(T),
+  Return(Value),
+  // A return of a call, with the evaluated callee and arguments. The call is made in place of the
+  // function that returns, see exec_tail_call.
+  TailCall(Rc<Function>, Arguments),
+}
+
+// The value of a normal outcome. Any other outcome is passed on to the caller right away.
+macro_rules! value {
+  ($outcome:expr) => {
+    match $outcome? {
+      Flow::Normal(value) => value,
+      Flow::Return(value) => return Ok(Flow::Return(value)),
+      Flow::TailCall(function, args) => return Ok(Flow::TailCall(function, args)),
+    }
+  };
+}
+
+// The slot of a function defined in a block, which the resolver turns into a variable holding a named lambda
+fn block_function(node: &Node) -> Option<Binding> {
+  match node {
+    Node::VariableDefine { children } => match (&children[0], &children[1]) {
+      (Node::Variable { binding, .. }, Node::Lambda { children }) if children.len() == 4 => Some(*binding),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+// Whether a top level item calls main. Calls in function bodies only run when the function is called.
+fn calls_main(node: &Node) -> bool {
+  match node {
+    Node::FunctionCall { name, .. } if name == b"main" => true,
+    Node::FunctionDefine { .. } | Node::Lambda { .. } => false,
+    _ => node.children().iter().any(calls_main),
+  }
+}
+
+// The call a return statement returns, if its expression is a call
+fn tail_call(node: &Node) -> Option<&Node> {
+  match node {
+    Node::Expression { children } => tail_call(&children[0]),
+    Node::FunctionCall { .. } | Node::Call { .. } => Some(node),
+    _ => None,
+  }
+}
+
+// The definitions and globals of a module that is not running at the moment
+#[derive(Debug, Default)]
+struct ModuleState {
+  functions: HashMap<String, Rc<Function>>,
+  structs: HashMap<String, Vec<String>>,
+  variants: HashMap<String, (String, Vec<String>)>,
+  globals: Vec<Option<Value>>,
+  global_names: Vec<String>,
+}
+
+// How number operations handle results that do not fit in a number
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Arithmetic {
+  // The operation fails with NumberOverflow above the largest number and NumberUnderflow below the smallest one
+  #[default]
+  Checked,
+  // The result wraps around, like a two's complement integer
+  Wrapping,
+  // The result is clamped to the largest or the smallest number
+  Saturating,
+  // The result becomes a big integer
+  Promoting,
+}
+
+impl Arithmetic {
+  // Turns the exact result of an operation into a value. Operations on two numbers are computed exactly in 64 bits.
+  fn value(self, exact: i64, span: Span) -> Result<Value, AsaErrorKind> {
+    match (i32::try_from(exact), self) {
+      (Ok(number), _) => Ok(Value::Number(number)),
+      (Err(_), Arithmetic::Checked) if exact > 0 => Err(AsaErrorKind::NumberOverflow(span)),
+      (Err(_), Arithmetic::Checked) => Err(AsaErrorKind::NumberUnderflow(span)),
+      (Err(_), Arithmetic::Wrapping) => Ok(Value::Number(exact as i32)),
+      (Err(_), Arithmetic::Saturating) => Ok(Value::Number(exact.clamp(i32::MIN.into(), i32::MAX.into()) as i32)),
+      (Err(_), Arithmetic::Promoting) => Ok(Value::BigInt(BigInt::from(exact))),
+    }
+  }
+
+  // Turns a number literal that is too large for a number into a value. Literals are never negative.
+  fn large_number(self, literal: &BigInt, span: Span) -> Result<Value, AsaErrorKind> {
+    match self {
+      Arithmetic::Checked => Err(AsaErrorKind::NumberOverflow(span)),
+      Arithmetic::Wrapping => Ok(Value::Number(literal.to_string().bytes()
+        .fold(0i32, |number, digit| number.wrapping_mul(10).wrapping_add((digit - b'0') as i32)))),
+      Arithmetic::Saturating => Ok(Value::Number(i32::MAX)),
+      Arithmetic::Promoting => Ok(Value::BigInt(literal.clone())),
+    }
+  }
+}
+
+// Maximum number of nested function calls
+pub const DEFAULT_MAX_DEPTH: usize = 500;
+
+// Maximum nesting of the nodes being evaluated, counting the nodes of every running function. The Rust stack a call
+// takes grows with the nesting of the expression the call is in, so calls alone do not bound it. A call takes about
+// six levels, its call node, the body and the expressions around the next call, so the default leaves room for the
+// maximum number of calls unless every call is nested deep inside its function.
+pub const DEFAULT_MAX_NESTING: usize = 10 * DEFAULT_MAX_DEPTH;
+
+// Stack size of a thread that can run the interpreter with the default limits. A level of nesting takes up to about
+// 5 KiB of stack in a debug build and a little less in a release build, so the defaults do not fit the 8 MiB main
+// thread or the 2 MiB of a spawned thread. A host runs exec on a thread built with this stack size, or lowers
+// the limits with set_max_depth and set_max_nesting.
+pub const DEFAULT_STACK_SIZE: usize = 32 * 1024 * 1024;
 
 #[derive(Debug)]
 pub struct Interpreter {
   // Function Table:
   // Key - Function name
-  // Value - Vec<Node> arguments, statements
-  functions: HashMap<String, (Arguments,Statements)>,
+  // Value - Function with the arguments and statements
+  functions: HashMap<String, Rc<Function>>,
+  // Type Table:
+  // Key - Struct name
+  // Value - Field names in declaration order
+  structs: HashMap<String, Vec<String>>,
+  // Variant Table:
+  // Key - Variant name, variants are used without the enum name
+  // Value - Enum name, field names of the variant
+  variants: HashMap<String, (String, Vec<String>)>,
   // Stack:
-  // Each element in the stack is a func tion stack frame.
+  // Each element in the stack is a function stack frame.
   // Crate a new stack frame on function entry.
   // Pop stack frame on function return.
-  // Key - Variable name
-  // Value - Variable value
+  // A frame holds the variables of the function in the slots the resolver assigned to them.
   stack: Vec<Frame>,
+  // Globals:
+  // The values of the top level variables, constants and import namespaces by slot, see Binding::Global.
+  // A global has no value until its definition runs. The names are kept by slot for the resolver and for module members.
+  globals: Vec<Option<Value>>,
+  global_names: Vec<String>,
+  // Call Chain:
+  // Names of the functions that are running, outermost first. A call beyond the maximum depth fails
+  // before the Rust stack of the host runs out.
+  calls: Vec<String>,
+  max_depth: usize,
+  // Number of nodes being evaluated, each inside the one before
+  nesting: usize,
+  max_nesting: usize,
+  arithmetic: Arithmetic,
+  // Modules:
+  // The tables above and the globals belong to the module that is running, identified by its path.
+  // The program passed to exec is the module with the path set by set_path, by default the empty path.
+  // The state of the other loaded modules is kept in the module table while they are not running.
+  module: String,
+  modules: HashMap<String, ModuleState>,
+  // Loaded modules by path, so every module is only loaded once
+  loaded: HashMap<String, Rc<Module>>,
+  // Modules whose import is in progress, to detect import cycles
+  loading: Vec<String>,
+  loader: Box<dyn ModuleLoader>,
+}
+
+impl Default for Interpreter {
+  fn default() -> Self {
+    Interpreter::new()
+  }
 }
 
 impl Interpreter {
 
   pub fn new() -> Interpreter {
+    Interpreter::with_loader(FileLoader)
+  }
+
+  // Creates an interpreter that loads imported modules with the given loader
+  pub fn with_loader(loader: impl ModuleLoader + 'static) -> Interpreter {
     Interpreter {
       functions: HashMap::new(),
-      stack: Vec::new(),
+      structs: HashMap::new(),
+      variants: HashMap::new(),
+      // The bottom frame holds the variables of the top level program.
+      stack: vec![Frame::default()],
+      globals: vec![],
+      global_names: vec![],
+      calls: vec![],
+      max_depth: DEFAULT_MAX_DEPTH,
+      nesting: 0,
+      max_nesting: DEFAULT_MAX_NESTING,
+      arithmetic: Arithmetic::default(),
+      module: String::new(),
+      modules: HashMap::new(),
+      loaded: HashMap::new(),
+      loading: vec![],
+      loader: Box::new(loader),
     }
   }
 
-  pub fn exec(&mut self, node: &Node) -> Result<Value,AsaErrorKind> {
-    //println!("Executing node: {:?}\n", node);
-    match node {
-      Node::Program{children} => {
-        for n in children {
-          match n {
-            Node::FunctionDefine { .. } =>
-            {
-              self.exec(n)?;
-              //println!("Functions registered: {:?}", self.functions);
-            }
-            |
-            Node::Expression{..} |
-            Node::VariableDefine{..} |
-            Node::String{..} |
-            Node::Number{..} |
-            Node::Bool{..} => {
-              return self.exec(n);
-            }
-            _ => unreachable!(),
-          }
-        }
-        Ok(Value::Bool(true))
-      },
+  // Sets the path of the program passed to exec. Imports are resolved relative to it.
+  pub fn set_path(&mut self, path: &str) {
+    self.module = path.to_string();
+  }
 
-      Node::MathExpression { name, children } => {
-        //println!("Current stack before evaluating MathExpression: {:?}", self.stack);
-    
-        let mut resolve_operand = |child: &Node| -> Result<Value, AsaErrorKind> {
-          match child {
-            Node::Identifier { value } => {
-              // Get the identifier name as a string
-              let identifier = std::str::from_utf8(value).unwrap();
-              // Retrieve the value from the stack
-              for frame in self.stack.iter().rev() {
-                if let Some(val) = frame.get(identifier) {
-                  return Ok(val.clone());
-                }
-              }
-              Err(AsaErrorKind::UndefinedFunction)
-            }
-            _ => self.exec(child), // Evaluate other node types normally
-          }
-        };
-    
-        let operand1 = resolve_operand(&children[0])?;
-        let operand2 = resolve_operand(&children[1])?;
-    
-        // Convert operands to displayable strings
-        let operand1_str = match &operand1 {
-          Value::Number(n) => n.to_string(),
-          Value::String(s) => s.clone(),
-          Value::Bool(b) => b.to_string(),
-        };
-    
-        let operand2_str = match &operand2 {
-          Value::Number(n) => n.to_string(),
-          Value::String(s) => s.clone(),
-          Value::Bool(b) => b.to_string(),
-        };
-        //println!("here3\n");
-    
-        // Type of operation
-        let operation = match std::str::from_utf8(name) {
-          Ok("add") => "+",
-          Ok("sub") => "-",
-          _ => return Err(AsaErrorKind::Generic("Unknown operation".into())),
-        };
-    
-        //println!("Evaluating expression: {} {} {}", operand1_str, operation, operand2_str);
-    
-        // Perform the operation
-        let result = match (operand1, operand2, name.as_slice()) {
-          (Value::Number(op1), Value::Number(op2), b"add") => Ok(Value::Number(op1 + op2)),
-          (Value::Number(op1), Value::Number(op2), b"sub") => Ok(Value::Number(op1 - op2)),
-          _ => Err(AsaErrorKind::Generic("MathOperationError".into())),
-        };
+  // Sets the maximum number of nested function calls. Every call takes Rust stack space, so a host that runs
+  // the interpreter on a thread with less than DEFAULT_STACK_SIZE of stack needs a lower limit than the default.
+  pub fn set_max_depth(&mut self, depth: usize) {
+    self.max_depth = depth;
+  }
 
-        result
-      }
+  // Sets the maximum nesting of the nodes being evaluated. Like the maximum depth, it has to fit the stack of the
+  // thread that runs the interpreter, and it should leave about ten levels for every call the maximum depth allows.
+  pub fn set_max_nesting(&mut self, nesting: usize) {
+    self.max_nesting = nesting;
+  }
+
+  // Sets how number operations handle results that do not fit in a number. By default they fail.
+  pub fn set_arithmetic(&mut self, arithmetic: Arithmetic) {
+    self.arithmetic = arithmetic;
+  }
+
+  // Reads a variable from the slot the resolver bound it to. A global is empty until its definition runs.
+  fn get_variable(&self, name: &[u8], binding: Binding, span: Span) -> Result<Value, AsaErrorKind> {
+    let frame = self.stack.last().ok_or(AsaErrorKind::Generic("No frame".to_string()))?;
+    let value = match binding {
+      Binding::Local(slot) => frame.slots.get(slot).cloned(),
+      Binding::Captured(position) => frame.function.as_ref().and_then(|function| function.environment.borrow().get(position).cloned()),
+      Binding::Global(slot) => self.globals.get(slot).cloned().flatten(),
+    };
+    value.ok_or_else(|| AsaErrorKind::VariableNotDefined { name: String::from_utf8_lossy(name).to_string(), span })
+  }
 
-      Node::ConditionalExpression { name, children } => {
-        if children.len() != 2 {
-          return Err(AsaErrorKind::Generic("Conditional expression must have exactly two operands".to_string()));
+  // Stores the value of a variable in its slot. The slots of a frame and the globals grow as variables are defined.
+  fn define_variable(&mut self, binding: Binding, value: Value) -> Result<(), AsaErrorKind> {
+    match binding {
+      Binding::Local(slot) => {
+        let frame = self.stack.last_mut().ok_or(AsaErrorKind::Generic("No frame".to_string()))?;
+        if slot >= frame.slots.len() {
+          frame.slots.resize(slot + 1, Value::Nil);
         }
-    
-        // Helper function to unwrap the expression node and resolve values
-        let mut resolve_value = |node: &Node| -> Result<Value, AsaErrorKind> {
-          match node {
-            Node::Expression { children } if !children.is_empty() => {
-              // Assume the first child is the actual value or identifier
-              let inner_node = &children[0];
-              match inner_node {
-                Node::ConditionalExpression { .. } => self.exec(inner_node),
-                Node::MathExpression { .. } => self.exec(inner_node),
-                Node::Identifier { value } => {
-                  let identifier = std::str::from_utf8(value).unwrap();
-                  // Search the stack for the identifier
-                  for frame in self.stack.iter().rev() {
-                    if let Some(val) = frame.get(identifier) {
-                      return Ok(val.clone());
-                    }
-                  }
-                  Err(AsaErrorKind::VariableNotDefined(identifier.to_string()))
-                },
-                _ => self.exec(inner_node),
-              }
-            },
-            _ => Err(AsaErrorKind::Generic("Expected an Expression node as child of ConditionalExpression".to_string())),
-          }
-        };
-    
-        let left_result = resolve_value(&children[0])?;
-        let right_result = resolve_value(&children[1])?;
-    
-        match (&left_result, &right_result) {
-          (Value::Number(left_val), Value::Number(right_val)) => match std::str::from_utf8(name) {
-            Ok("gt_") => Ok(Value::Bool(left_val > right_val)),
-            Ok("lt_") => Ok(Value::Bool(left_val < right_val)),
-            Ok("gte") => Ok(Value::Bool(left_val >= right_val)),
-            Ok("lte") => Ok(Value::Bool(left_val <= right_val)),
-            Ok("eq_") => Ok(Value::Bool(left_val == right_val)),
-            Ok("neq") => Ok(Value::Bool(left_val != right_val)),
-            _ => Err(AsaErrorKind::Generic("Unknown conditional operator".to_string())),
-          },
-          (Value::Bool(left_bool), Value::Bool(right_bool)) => match std::str::from_utf8(name) {
-            Ok("eq_") => Ok(Value::Bool(*left_bool == *right_bool)),
-            Ok("neq") => Ok(Value::Bool(*left_bool != *right_bool)),
-            _ => Err(AsaErrorKind::Generic("Unsupported operator for boolean comparison".to_string())),
-          },
-          _ => Err(AsaErrorKind::Generic("Mismatched types in conditional expression".to_string())),
+        frame.slots[slot] = value;
+      }
+      Binding::Global(slot) => {
+        if slot >= self.globals.len() {
+          self.globals.resize(slot + 1, None);
         }
-      } 
+        self.globals[slot] = Some(value);
+      }
+      Binding::Captured(_) => return Err(AsaErrorKind::Generic("Captured variables cannot be defined".to_string())),
+    }
+    Ok(())
+  }
 
-      // Defines a function that takes some arguments and executes a program based on those arguments. 
-      // The code first checks if the function exists, and if it does, it creates a new scope in which to execute the function's statements (push a new Frame onto the interpreter stack). 
-      // The code then executes each statement in the function's statements list and returns the result of the function's execution. 
-      // You will have to correlate each passed value with the apprpriate variable in the called function. If the wrong number or an wrong type of variable is passed, return an error. 
-      // On success, insert the return value of the function (if any) into the appropriate entry of the caller's stack.
-      Node::FunctionCall { name, children } => {
-        let func_name = std::str::from_utf8(name).unwrap();
-        //println!("Executing function call: {}\n", func_name);
+  // Binds a global by name. Import namespaces are bound before the resolver runs, which then finds them by name.
+  fn define_global(&mut self, name: String, value: Value) -> Result<(), AsaErrorKind> {
+    let slot = match self.global_names.iter().position(|global| *global == name) {
+      Some(slot) => slot,
+      None => {
+        self.global_names.push(name);
+        self.global_names.len() - 1
+      }
+    };
+    self.define_variable(Binding::Global(slot), value)
+  }
 
-        // Print the children at the beginning
-        //println!("Input children: {:?}\n", children);
+  // Executes the statements of a block or function body. The value of the block is the value of its last child.
+  // The variables of the block are not visible after it, the resolver gives their slots to the code that follows.
+  // The functions defined in the block can call each other. Until its definition runs, a function is nil for
+  // the functions of the block defined before it.
+  fn exec_block(&mut self, children: &[Node]) -> Result<Flow, AsaErrorKind> {
+    let functions = children.iter().filter(|child| block_function(child).is_some()).count();
+    if functions > 1 {
+      for child in children {
+        if let Some(binding) = block_function(child) {
+          self.define_variable(binding, Value::Nil)?;
+        }
+      }
+    }
+    let mut result = Ok(Flow::Normal(Value::Nil));
+    for statement in children {
+      result = self.eval(statement);
+      if !matches!(result, Ok(Flow::Normal(_))) {
+        break;
+      }
+      if functions > 1 {
+        if let Some(Binding::Local(slot)) = block_function(statement) {
+          self.complete_functions(children, slot);
+        }
+      }
+    }
+    result
+  }
 
-        // Fetch function arguments and body
-        let (func_args, func_body) = self.functions
-            .get(func_name)
-            .map(|(args, body)| (args.clone(), body.clone()))
-            .ok_or(AsaErrorKind::UndefinedFunction)?;
+  // Gives the function just defined in the slot to the functions of the block defined before it that capture it
+  fn complete_functions(&mut self, children: &[Node], slot: usize) {
+    let Some(frame) = self.stack.last() else { return };
+    let Some(function) = frame.slots.get(slot) else { return };
+    for sibling in children.iter().filter_map(block_function) {
+      let Binding::Local(sibling) = sibling else { continue };
+      if let Some(Value::Function(sibling)) = frame.slots.get(sibling).filter(|_| sibling != slot) {
+        let mut environment = sibling.environment.borrow_mut();
+        for (position, captured) in sibling.captured.iter().enumerate() {
+          if *captured == Binding::Local(slot) {
+            environment[position] = function.clone();
+          }
+        }
+      }
+    }
+  }
 
-        // Print func_args and func_body
-        //println!("Function arguments (func_args): {:?}", func_args);
-        //println!("Function body (func_body): {:?}", func_body);
+  // Builds an interpolated string. Every part is evaluated and appended in its display form.
+  fn exec_interpolation(&mut self, children: &[Node]) -> Result<Flow, AsaErrorKind> {
+    let mut result = String::new();
+    for child in children {
+      result.push_str(&value!(self.eval(child)).to_string());
+    }
+    Ok(Flow::Normal(Value::String(result)))
+  }
 
-        let mut stack_frame = Frame::new();
+  // Runs the body of a try expression. If it fails, the handler runs with the error bound to the name:
+  // a thrown value as it is, a runtime error as an Error struct with its kind and message. The finally block runs
+  // last in any case, also when the body or the handler returns. An error or a return in the finally block replaces
+  // the result of the body or the handler.
+  fn exec_try(&mut self, children: &[Node]) -> Result<Flow, AsaErrorKind> {
+    // A call returned from the body is made inside the try expression, so its errors are caught
+    let result = match self.eval(&children[0]).and_then(|flow| self.finish_tail_call(flow)) {
+      Err(error) => self.define_variable(binding(&children[1])?, error_value(error))
+        .and_then(|_| self.eval(&children[2]))
+        .and_then(|flow| self.finish_tail_call(flow)),
+      result => result,
+    };
+    if let Some(finally) = children.get(3) {
+      value!(self.eval(finally));
+    }
+    result
+  }
 
-        // Define persistent empty vectors
-        let empty_args: Vec<Node> = Vec::new();
-        let actual_args = if let Some(Node::FunctionArguments { children }) = children.first() {
-          children
-        } else {
-          &empty_args
-        };
+  // Evaluates the scrutinee (first child) and runs the arms against its value. The bindings of an arm are visible
+  // to the guard and the body of the arm.
+  fn exec_match(&mut self, children: &[Node], span: Span) -> Result<Flow, AsaErrorKind> {
+    let value = value!(self.eval(&children[0]));
+    for arm in &children[1..] {
+      let children = match arm {
+        Node::MatchArm {children} => children,
+        _ => return Err(AsaErrorKind::Generic("Expected a match arm".to_string())),
+      };
+      let mut bindings = vec![];
+      if !self.match_pattern(&children[0], &value, &mut bindings)? {
+        continue;
+      }
+      if let Some(value) = value!(self.exec_arm(children, bindings)) {
+        return Ok(Flow::Normal(value));
+      }
+    }
+    Err(AsaErrorKind::NonExhaustiveMatch(span))
+  }
 
-        let expected_args = match func_args {
-          Node::FunctionArguments { ref children } => children,
-          _ => &empty_args,
-        };
+  // Binds the names of a matching pattern and runs the guard and body of the arm.
+  // Returns None if the guard does not hold, so the next arm is tried.
+  fn exec_arm(&mut self, children: &[Node], bindings: Vec<(Binding, Value)>) -> Result<Flow<Option<Value>>, AsaErrorKind> {
+    for (binding, value) in bindings {
+      self.define_variable(binding, value)?;
+    }
+    if let [_, Node::MatchGuard {children: guard}, _] = children {
+      match value!(self.eval(&guard[0])) {
+        Value::Bool(true) => {},
+        Value::Bool(false) => return Ok(Flow::Normal(None)),
+        _ => return Err(AsaErrorKind::Generic("Match guard must be a boolean".to_string())),
+      }
+    }
+    Ok(Flow::Normal(Some(value!(self.eval(&children[children.len() - 1])))))
+  }
 
-        // Compare the number of arguments, but allow zero arguments
-        if expected_args.len() != actual_args.len() {
-          println!(
-            "Function '{}' called with an incorrect number of arguments. Expected {}, got {}",
-            func_name, expected_args.len(), actual_args.len()
-          );
+  // Checks whether the value matches the pattern, collecting the names bound by the pattern.
+  fn match_pattern(&self, pattern: &Node, value: &Value, bindings: &mut Vec<(Binding, Value)>) -> Result<bool, AsaErrorKind> {
+    match pattern {
+      Node::Wildcard => Ok(true),
+      Node::Number {value: n} => Ok(equal(value, &Value::Number(*n))),
+      Node::BigInt {value: n} | Node::LargeNumber {value: n, ..} => Ok(equal(value, &Value::BigInt(n.clone()))),
+      Node::Bool {value: b} => Ok(*value == Value::Bool(*b)),
+      Node::String {value: s} => Ok(*value == Value::String(s.clone())),
+      // The resolver leaves the names of variants without fields, they are matched by name
+      Node::Identifier {value: name, ..} => {
+        let name = String::from_utf8_lossy(name).to_string();
+        Ok(matches!(value, Value::Variant(_, variant, _) if *variant == name))
+      }
+      Node::Variable {binding, ..} => {
+        bindings.push((*binding, value.clone()));
+        Ok(true)
+      }
+      Node::VariantPattern {name, children} => {
+        let name = String::from_utf8_lossy(name).to_string();
+        let (_, fields) = self.variants.get(&name)
+          .ok_or(AsaErrorKind::UndefinedType(name.clone()))?;
+        if fields.len() != children.len() {
           return Err(AsaErrorKind::Generic(format!(
-            "Function '{}' called with an incorrect number of arguments",
-            func_name
+            "Pattern for variant '{}' has {} fields, expected {}", name, children.len(), fields.len()
           )));
         }
-
-        // Map arguments
-        for (arg_node, arg_value_node) in expected_args.iter().zip(actual_args.iter()) {
-
-          let arg_value = if let Node::Expression { children } = arg_value_node {
-            if let Some(node) = children.first() {
-              match node {
-                // Check for math expression
-                Node::MathExpression { .. } => self.exec(node)?,
-                // Check for conditional expression
-                Node::ConditionalExpression { .. } => self.exec(node)?,
-                // Other nodes
-                _ => self.exec(arg_value_node)?
+        match value {
+          Value::Variant(_, variant, values) if *variant == name => {
+            for (field_pattern, field_value) in children.iter().zip(values) {
+              if !self.match_pattern(field_pattern, field_value, bindings)? {
+                return Ok(false);
               }
-            } else {
-              // No children in expression
-              return Err(AsaErrorKind::Generic("Expected an expression node with children".into()));
-            }
-          } else {
-            self.exec(arg_value_node)?
-          };
-
-          // Extract the identifier 
-          let identifier_node = if let Node::Expression { children } = arg_node {
-            if let Some(Node::Identifier { value }) = children.first() {
-              value.as_slice()
-            } else {
-              return Err(AsaErrorKind::Generic("Expected identifier inside expression".into()));
             }
-          } else {
-            return Err(AsaErrorKind::Generic("Expected expression node".into()));
-          };
-
-          let arg_name = std::str::from_utf8(identifier_node).unwrap();
-          stack_frame.insert(arg_name.to_string(), arg_value);
+            Ok(true)
+          }
+          _ => Ok(false),
         }
+      }
+      _ => Err(AsaErrorKind::Generic("Invalid pattern".to_string())),
+    }
+  }
 
-        // Push new stack frame
-        self.stack.push(stack_frame);
-        //println!("Stack after pushing new frame: {:?}", self.stack);
+  // Runs a node and returns its value. The value of a return is the value of the function call it ends.
+  pub fn exec(&mut self, node: &Node) -> Result<Value,AsaErrorKind> {
+    match self.eval(node)? {
+      Flow::Normal(value) | Flow::Return(value) => Ok(value),
+      Flow::TailCall(function, args) => self.call_function(&function, args),
+    }
+  }
 
-        // Execute body
-        let mut final_result = Value::Bool(true);
-        if let Node::FunctionStatements { children } = func_body {
-          for statement in children {
-            final_result = self.exec(&statement)?;
-          }
-        }
+  // Runs a node, unless it would be nested deeper than the maximum nesting. The error names the innermost running
+  // function, a program that nests too deep outside of functions fails in <top level>.
+  fn eval(&mut self, node: &Node) -> Result<Flow, AsaErrorKind> {
+    if self.nesting >= self.max_nesting {
+      return Err(self.nested_too_deep());
+    }
+    self.nesting += 1;
+    let flow = self.eval_node(node);
+    self.nesting -= 1;
+    flow
+  }
 
-        // Pop the stack frame
-        self.stack.pop();
-        Ok(final_result)
-      }
+  #[cold]
+  fn nested_too_deep(&self) -> AsaErrorKind {
+    let function = self.calls.last().cloned().unwrap_or("<top level>".to_string());
+    AsaErrorKind::StackOverflow { function, chain: self.calls.clone() }
+  }
+
+  // Nodes that contain other nodes pass a return in one of them on, see Flow.
+  // Every call of an Asa function goes through here several times, so the arms hand their work to helper methods
+  // to keep the Rust stack frame of eval_node small.
+  fn eval_node(&mut self, node: &Node) -> Result<Flow, AsaErrorKind> {
+    //println!("Executing node: {:?}\n", node);
+    match node {
+      // Runs the program, see exec_program.
+      Node::Program{..} => self.exec_program(node).map(Flow::Normal),
+
+      // Loads the module and binds its namespace as a global. The namespace is named after the module file
+      // unless the child gives a name.
+      Node::Import {name, children} => self.exec_import(name, children).map(Flow::Normal),
+
+      Node::MathExpression { name, children, span } => self.exec_math(name, children, *span),
+
+      Node::ConditionalExpression { name, children, .. } => self.exec_conditional(name, children),
+
+      // Defines a function that takes some arguments and executes a program based on those arguments. 
+      // The code first checks if the function exists, and if it does, it creates a new scope in which to execute the function's statements (push a new Frame onto the interpreter stack). 
+      // The code then executes each statement in the function's statements list and returns the result of the function's execution. 
+      // You will have to correlate each passed value with the apprpriate variable in the called function. If the wrong number or an wrong type of variable is passed, return an error. 
+      // On success, insert the return value of the function (if any) into the appropriate entry of the caller's stack.
+      Node::FunctionCall { name, children, .. } => self.exec_function_call(name, children),
+
+      // Calls the value of an arbitrary expression (first child) with the arguments (second child).
+      Node::Call { children } => self.exec_call(children),
+
+      // Creates a function value that captures the variables listed by the resolver, see exec_lambda.
+      Node::Lambda { children } => self.exec_lambda(children),
     
       // Defines a new function based on the elements in the children argument. 
       // The name of the function is retrieved from the node struct, the arguments are the first child, and the statements that define the function are the second child. 
       // A new key-value pair is then inserted into the functions table of the interprer. 
       // If the function was successfully defined, the code returns a Value object with a boolean value of true, otherwise an error is returned.
-      Node::FunctionDefine {name, children} => {
-        let function_name = std::str::from_utf8(name).unwrap();
-        let function = (children[0].clone(), children[1].clone());
-        if self.functions.contains_key(function_name) {
-          return Err(AsaErrorKind::Generic(format!("Function '{}' redefined", function_name)));
-        }
-        self.functions.insert(function_name.to_string(), function);
-        //println!("Current functions: {:?}\n", self.functions);
-        Ok(Value::Bool(true))
-      },
+      Node::FunctionDefine {name, children} => self.define_function(name, children).map(Flow::Normal),
 
-      // Calls the exec() method on the first element in the children argument, which recursively evaluates the AST of the program being executed and 
-      // returns the resulting value or error message.
-      Node::FunctionReturn {children} => {
-        if let Some(frame) = self.stack.last() {
-          let value = self.exec(&children[0])?;
-          return Ok(value.clone());
-        }
-        Err(AsaErrorKind::Generic("Invalid return".to_string()))
-      },
+      // Registers a struct type in the type table. The children are the identifiers of the fields.
+      Node::StructDefine {name, children} => self.define_struct(name, children).map(Flow::Normal),
 
-      // Retrieves the value of the identifier from the current frame on the stack. If the variable is defined in the current frame, the code returns its value. If the variable is not defined in the current frame, the code returns an error message.
-      Node::Identifier { value } => {
-        if let Some(frame) = self.stack.last() {
-          if let Some(val) = frame.get(std::str::from_utf8(value).unwrap()) {
-            return Ok(val.clone());
-          }
-        }
-        Err(AsaErrorKind::UndefinedFunction)
-      },
+      // Creates an instance of a struct. Every declared field needs exactly one initializer,
+      // the children are KeyValue nodes with the field identifier and the value expression.
+      Node::StructLiteral {name, children} => self.exec_struct_literal(name, children),
 
-      // Checks the type of the first element in the children argument and deciding what to do based on that type. 
-      // If the type is a VariableDefine or FunctionReturn node, the code runs the run method on that node and returns the result.
-      Node::FunctionStatements {children} => {
-        match &children[0] {
-          Node::VariableDefine {children} => {
-            let variable_name = match &children[0] {
-              Node::Identifier {value} => String::from_utf8_lossy(value).to_string(),
-              _ => return Err(AsaErrorKind::Generic("Invalid variable definition".to_string())),
-            };
-
-            let value = self.exec(&children[1])?;
-            self.stack.push(HashMap::new());
-            let frame = self.stack.last_mut().ok_or(AsaErrorKind::Generic("No frame".to_string()))?;
-            frame.insert(variable_name, value.clone());
-            Ok(value)
-          }
-          Node::FunctionReturn {children} => self.exec(&Node::FunctionReturn {children: children.to_vec()}),
-          _ => Err(AsaErrorKind::Generic("Invalid statement".to_string())),
-        }
-      },
+      // Registers the variants of an enum in the variant table. Variant names have to be unique across all enums,
+      // since they are used without the enum name.
+      Node::EnumDefine {name, children} => self.define_enum(name, children).map(Flow::Normal),
+
+      // Runs the body (first child) and catches its errors, see exec_try.
+      Node::Try {children} => self.exec_try(children),
+
+      // Fails with the value of the child, which a surrounding try expression can catch.
+      Node::Throw {children} => self.exec_throw(children),
+
+      // Evaluates the scrutinee (first child) and runs the first arm whose pattern matches and whose guard holds.
+      Node::Match {children, span} => self.exec_match(children, *span),
+
+      // Reads a field of the struct the child evaluates to.
+      Node::FieldAccess {name, children} => self.exec_field_access(name, children),
+
+      // Evaluates the child and ends the function with its value, see exec_return.
+      Node::FunctionReturn {children} => self.exec_return(children),
+
+      // Retrieves the value of the variable from the slot the resolver bound it to. A global whose definition has not run yet is an error.
+      Node::Variable { name, binding, span } => self.get_variable(name, *binding, *span).map(Flow::Normal),
+
+      // The resolver leaves the names that are not variables: enum variants without fields and functions.
+      Node::Identifier { value, span } => self.exec_identifier(value, *span).map(Flow::Normal),
+
+      // Function bodies and block expressions share the same machinery: the statements run in order
+      // and the value of the last statement is the value of the block.
+      Node::FunctionStatements {children} |
+      Node::Block {children} => self.exec_block(children),
 
       // Defines a new variable by assigning a name and a value to it. 
       // The name is retrieved from the first element of the children argument, and the value is retrieved by running the run method on the second element of the children argument. 
-      // The key-value pair is then inserted into the last frame on the stack field of the current runtime object.
-      Node::VariableDefine {children} => {
-        // Variable name
-        let name = match &children[0] {
-          Node::Identifier {value} => String::from_utf8_lossy(value).to_string(),
-          _ => return Err(AsaErrorKind::Generic("Invalid variable definition".to_string())),
-        };
-    
-        // Variable value
-        let value = self.exec(&children[1])?;
-        // println!("Assigning Variable: {} = {:?}", name, value);  // Print the variable assignment
-        self.stack.push(HashMap::new());
-        let frame = self.stack.last_mut().ok_or(AsaErrorKind::Generic("No frame".to_string()))?;
-        frame.insert(name, value.clone());
-        Ok(value)
-      }
+      // The value is then stored in the slot of the variable, in the current frame or in the globals.
+      Node::VariableDefine {children} => self.exec_variable_define(children),
+
+      // Evaluates each element expression and collects the values into a new list.
+      Node::List {children} => self.exec_list(children),
 
-      // Evaluate the child node using the exec() method.
+      // Evaluates each key value pair and collects them into a new map. A repeated key replaces the earlier value.
+      Node::Map {children} => self.exec_map(children),
+
+      // Evaluates the indexed collection (first child) and the index (second child) and reads the element.
+      Node::Index {children} => self.exec_index(children),
+
+      // Stores the value (second child) into the element of a collection or the field of a struct. The target (first child) is an Index or FieldAccess node.
+      // Since collections and structs are shared, updating the evaluated target updates the variable it came from.
+      Node::Assignment {children} => self.exec_assignment(children),
+
+      // Evaluate the child node using the eval() method.
       Node::Expression{children} => {
-        self.exec(&children[0])
+        self.eval(&children[0])
       }
       Node::Number{value} => {
-        Ok(Value::Number(*value))
+        Ok(Flow::Normal(Value::Number(*value)))
+      }
+      Node::BigInt{value} => {
+        Ok(Flow::Normal(Value::BigInt(value.clone())))
       }
+      Node::LargeNumber{value, span} => self.arithmetic.large_number(value, *span).map(Flow::Normal),
       Node::String{value} => {
-        Ok(Value::String(value.clone()))
+        Ok(Flow::Normal(Value::String(value.clone())))
       }
+      // Evaluates the pieces of an interpolated string in the current scope and joins their display forms.
+      Node::Interpolation{children} => self.exec_interpolation(children),
       Node::Bool{value} => {
-        Ok(Value::Bool(*value))
+        Ok(Flow::Normal(Value::Bool(*value)))
+      }
+      Node::Null => {
+        Ok(Flow::Normal(Value::Nil))
       }
       // Return an error message.
-      x => {
+      _ => {
         Err(AsaErrorKind::Generic("Error".to_string()))
       },
     }
     //println!("Stack after execution: {:?}", self.stack);
   }
 
+  // Runs the top level items, see exec_items. If the program defines a main function, it is called afterwards
+  // and its result is the result of the program. Otherwise the result is the value of the last item.
+  // A program that calls main itself at the top level is not run again, its result is the value of the last item.
+  fn exec_program(&mut self, node: &Node) -> Result<Value, AsaErrorKind> {
+    let result = self.exec_items(node)?;
+    if self.functions.contains_key("main") && !node.children().iter().any(calls_main) {
+      return self.start_main(vec![]);
+    }
+    Ok(result)
+  }
+
+  fn exec_call(&mut self, children: &[Node]) -> Result<Flow, AsaErrorKind> {
+    let callee = value!(self.eval(&children[0]));
+    let args = value!(self.exec_arguments(&children[1..]));
+    self.call_value(callee, args).map(Flow::Normal)
+  }
+
+  // The variables the lambda captures (third child) are read from the current frame. A function defined in a block
+  // is a lambda with its name as the last child.
+  fn exec_lambda(&mut self, children: &[Node]) -> Result<Flow, AsaErrorKind> {
+    let mut environment = vec![];
+    let mut bindings = vec![];
+    for captured in children.get(2).map(Node::children).unwrap_or(&[]) {
+      environment.push(value!(self.eval(captured)));
+      bindings.push(binding(captured)?);
+    }
+    let name = match children.get(3) {
+      Some(Node::Identifier { value, .. }) => Some(String::from_utf8_lossy(value).to_string()),
+      _ => None,
+    };
+    Ok(Flow::Normal(Value::Function(Rc::new(Function {
+      name,
+      arguments: children[0].clone(),
+      statements: children[1].clone(),
+      environment: RefCell::new(environment),
+      captured: bindings,
+      module: self.module.clone(),
+    }))))
+  }
+
+  fn exec_throw(&mut self, children: &[Node]) -> Result<Flow, AsaErrorKind> {
+    let value = value!(self.eval(&children[0]));
+    Err(AsaErrorKind::Thrown(value))
+  }
+
+  fn exec_field_access(&mut self, name: &[u8], children: &[Node]) -> Result<Flow, AsaErrorKind> {
+    let target = value!(self.eval(&children[0]));
+    get_field(&target, &String::from_utf8_lossy(name)).map(Flow::Normal)
+  }
+
+  // The statements after the return do not run, the blocks and expressions around it are left until the function call is reached.
+  // The bottom frame belongs to the top level of the program, which is not a function.
+  fn exec_return(&mut self, children: &[Node]) -> Result<Flow, AsaErrorKind> {
+    if self.stack.len() < 2 {
+      return Err(AsaErrorKind::Generic("Invalid return".to_string()));
+    }
+    if let Some(call) = tail_call(&children[0]) {
+      return self.exec_tail_call(call);
+    }
+    let value = value!(self.eval(&children[0]));
+    Ok(Flow::Return(value))
+  }
+
+  // Evaluates the callee and the arguments of a call in tail position. The call itself is made by call_function
+  // after the frame of the returning function is gone, so tail recursion runs in constant stack space.
+  // Enum variants and builtins are not Asa functions and are called right away.
+  fn exec_tail_call(&mut self, call: &Node) -> Result<Flow, AsaErrorKind> {
+    let (function, children) = match call {
+      Node::FunctionCall { name, children, .. } => {
+        let func_name = std::str::from_utf8(name).unwrap();
+        match self.callee(func_name)? {
+          Some(function) => (function, children.as_slice()),
+          None => {
+            let value = value!(self.exec_builtin_call(func_name, children));
+            return Ok(Flow::Return(value));
+          }
+        }
+      }
+      Node::Call { children } => match value!(self.eval(&children[0])) {
+        Value::Function(function) => (function, &children[1..]),
+        _ => return Err(AsaErrorKind::Generic("Called value is not a function".to_string())),
+      },
+      _ => return Err(AsaErrorKind::Generic("Expected a call".to_string())),
+    };
+    let args = value!(self.exec_arguments(children));
+    Ok(Flow::TailCall(function, args))
+  }
+
+  // Makes the call of a tail call right away, for the places a call in tail position cannot leave
+  fn finish_tail_call(&mut self, flow: Flow) -> Result<Flow, AsaErrorKind> {
+    match flow {
+      Flow::TailCall(function, args) => self.call_function(&function, args).map(Flow::Return),
+      flow => Ok(flow),
+    }
+  }
+
+  fn exec_variable_define(&mut self, children: &[Node]) -> Result<Flow, AsaErrorKind> {
+    // Variable slot
+    let binding = binding(&children[0])?;
+
+    // Variable value
+    let value = value!(self.eval(&children[1]));
+    // println!("Assigning Variable: {:?} = {:?}", binding, value);  // Print the variable assignment
+    self.define_variable(binding, value)?;
+    Ok(Flow::Normal(Value::Nil))
+  }
+
+  fn exec_list(&mut self, children: &[Node]) -> Result<Flow, AsaErrorKind> {
+    let mut items = vec![];
+    for child in children {
+      items.push(value!(self.eval(child)));
+    }
+    Ok(Flow::Normal(Value::list(items)))
+  }
+
+  fn exec_map(&mut self, children: &[Node]) -> Result<Flow, AsaErrorKind> {
+    let map = Value::map(vec![]);
+    for child in children {
+      if let Node::KeyValue {children} = child {
+        let key = value!(self.eval(&children[0]));
+        let value = value!(self.eval(&children[1]));
+        set_index(&map, &key, value)?;
+      }
+    }
+    Ok(Flow::Normal(map))
+  }
+
+  fn exec_index(&mut self, children: &[Node]) -> Result<Flow, AsaErrorKind> {
+    let target = value!(self.eval(&children[0]));
+    let index = value!(self.eval(&children[1]));
+    get_index(&target, &index).map(Flow::Normal)
+  }
+
+  fn exec_import(&mut self, name: &[u8], children: &[Node]) -> Result<Value, AsaErrorKind> {
+    let path = resolve_path(&self.module, &String::from_utf8_lossy(name));
+    let namespace = match children.first() {
+      Some(Node::Identifier {value, .. }) => String::from_utf8_lossy(value).to_string(),
+      _ => module_name(&path)?,
+    };
+    let module = self.load_module(&path)?;
+    self.define_global(namespace, Value::Module(module))?;
+    Ok(Value::Nil)
+  }
+
+  // A number result that does not fit in a number is handled by the arithmetic mode, see Arithmetic
+  fn exec_math(&mut self, name: &[u8], children: &[Node], span: Span) -> Result<Flow, AsaErrorKind> {
+    let operand1 = value!(self.eval(&children[0]));
+    let operand2 = value!(self.eval(&children[1]));
+
+    // Perform the operation
+    let result = match (operand1, operand2, name) {
+      (Value::Number(op1), Value::Number(op2), b"add") => self.arithmetic.value(i64::from(op1) + i64::from(op2), span),
+      (Value::Number(op1), Value::Number(op2), b"sub") => self.arithmetic.value(i64::from(op1) - i64::from(op2), span),
+      (Value::Number(op1), Value::Number(op2), b"mul") => self.arithmetic.value(i64::from(op1) * i64::from(op2), span),
+      (Value::Number(_), Value::Number(0), b"div") => Err(AsaErrorKind::DivisionByZero),
+      (Value::Number(op1), Value::Number(op2), b"div") => self.arithmetic.value(i64::from(op1) / i64::from(op2), span),
+      (Value::String(op1), Value::String(op2), b"add") => Ok(Value::String(op1 + &op2)),
+      (op1, op2, name) => match (integer(&op1), integer(&op2)) {
+        (Some(op1), Some(op2)) => big_math(&op1, &op2, name),
+        _ => Err(AsaErrorKind::Generic("MathOperationError".into())),
+      },
+    };
+    result.map(Flow::Normal)
+  }
+
+  fn exec_conditional(&mut self, name: &[u8], children: &[Node]) -> Result<Flow, AsaErrorKind> {
+    if children.len() != 2 {
+      return Err(AsaErrorKind::Generic("Conditional expression must have exactly two operands".to_string()));
+    }
+
+    let left_result = value!(self.resolve_conditional_operand(&children[0]));
+    let right_result = value!(self.resolve_conditional_operand(&children[1]));
+
+    // Equality is defined for every pair of values. Values of different types are never equal, except for numbers
+    // and big integers. Lists, maps, structs and variants are compared structurally.
+    let result = match name {
+      b"eq_" => equal(&left_result, &right_result),
+      b"neq" => !equal(&left_result, &right_result),
+      _ => {
+        // Ordering is defined for numbers and big integers, and lexicographically for strings
+        let ordering = match (&left_result, &right_result) {
+          (Value::Number(left_val), Value::Number(right_val)) => left_val.cmp(right_val),
+          (Value::String(left_val), Value::String(right_val)) => left_val.cmp(right_val),
+          _ => match (integer(&left_result), integer(&right_result)) {
+            (Some(left_val), Some(right_val)) => left_val.cmp(&right_val),
+            _ => return Err(AsaErrorKind::Generic("Mismatched types in conditional expression".to_string())),
+          },
+        };
+        match name {
+          b"gt_" => ordering.is_gt(),
+          b"lt_" => ordering.is_lt(),
+          b"gte" => ordering.is_ge(),
+          b"lte" => ordering.is_le(),
+          _ => return Err(AsaErrorKind::Generic("Unknown conditional operator".to_string())),
+        }
+      }
+    };
+    Ok(Flow::Normal(Value::Bool(result)))
+  }
+
+  fn exec_function_call(&mut self, name: &[u8], children: &[Node]) -> Result<Flow, AsaErrorKind> {
+    let func_name = std::str::from_utf8(name).unwrap();
+    //println!("Executing function call: {}\n", func_name);
+
+    // Print the children at the beginning
+    //println!("Input children: {:?}\n", children);
+
+    match self.callee(func_name)? {
+      Some(function) => {
+        let args = value!(self.exec_arguments(children));
+        self.call_function(&function, args).map(Flow::Normal)
+      }
+      None => self.exec_builtin_call(func_name, children),
+    }
+  }
+
+  // The function a call by name refers to. Calls of variables are Call nodes after resolution, so the name is looked up
+  // in the function table. The enum variants and builtins come last, for them there is no function.
+  fn callee(&self, func_name: &str) -> Result<Option<Rc<Function>>, AsaErrorKind> {
+    match self.functions.get(func_name) {
+      Some(function) => Ok(Some(function.clone())),
+      None if self.variants.contains_key(func_name) || builtin(func_name).is_some() => Ok(None),
+      None => Err(AsaErrorKind::UndefinedFunction),
+    }
+  }
+
+  // Constructs an enum variant or calls a builtin. Kept apart from exec_function_call, which is on the Rust stack
+  // for most calls of Asa functions.
+  fn exec_builtin_call(&mut self, func_name: &str, children: &[Node]) -> Result<Flow, AsaErrorKind> {
+    if let Some((enum_name, fields)) = self.variants.get(func_name).cloned() {
+      let values = value!(self.exec_arguments(children)).positional(func_name)?;
+      if values.len() != fields.len() {
+        return Err(AsaErrorKind::Generic(format!(
+          "Variant '{}' called with an incorrect number of arguments",
+          func_name
+        )));
+      }
+      return Ok(Flow::Normal(Value::Variant(enum_name, func_name.to_string(), values)));
+    }
+    match builtin(func_name) {
+      Some(builtin) => {
+        let values = value!(self.exec_arguments(children)).positional(func_name)?;
+        builtin(values).map(Flow::Normal)
+      }
+      None => Err(AsaErrorKind::UndefinedFunction),
+    }
+  }
+
+  fn define_function(&mut self, name: &[u8], children: &[Node]) -> Result<Value, AsaErrorKind> {
+    let function_name = std::str::from_utf8(name).unwrap();
+    if self.functions.contains_key(function_name) {
+      return Err(AsaErrorKind::Generic(format!("Function '{}' redefined", function_name)));
+    }
+    self.install_function(name, children);
+    //println!("Current functions: {:?}\n", self.functions);
+    Ok(Value::Nil)
+  }
+
+  // Puts a top level function into the function table, replacing the function with the same name
+  fn install_function(&mut self, name: &[u8], children: &[Node]) {
+    let function_name = String::from_utf8_lossy(name).to_string();
+    let function = Rc::new(Function {
+      name: Some(function_name.clone()),
+      arguments: children[0].clone(),
+      statements: children[1].clone(),
+      environment: RefCell::new(vec![]),
+      captured: vec![],
+      module: self.module.clone(),
+    });
+    self.functions.insert(function_name, function);
+  }
+
+  fn define_struct(&mut self, name: &[u8], children: &[Node]) -> Result<Value, AsaErrorKind> {
+    let struct_name = std::str::from_utf8(name).unwrap();
+    if self.structs.contains_key(struct_name) {
+      return Err(AsaErrorKind::Generic(format!("Struct '{}' redefined", struct_name)));
+    }
+    let mut fields = vec![];
+    for child in children {
+      if let Node::Identifier {value, .. } = child {
+        fields.push(String::from_utf8_lossy(value).to_string());
+      }
+    }
+    self.structs.insert(struct_name.to_string(), fields);
+    Ok(Value::Nil)
+  }
+
+  fn exec_struct_literal(&mut self, name: &[u8], children: &[Node]) -> Result<Flow, AsaErrorKind> {
+    let type_name = String::from_utf8_lossy(name).to_string();
+    let fields = self.structs.get(&type_name)
+      .cloned()
+      .ok_or(AsaErrorKind::UndefinedType(type_name.clone()))?;
+    let mut values: Vec<Option<Value>> = vec![None; fields.len()];
+    for child in children {
+      if let Node::KeyValue {children} = child {
+        let field = match &children[0] {
+          Node::Identifier {value, .. } => String::from_utf8_lossy(value).to_string(),
+          _ => return Err(AsaErrorKind::Generic("Invalid field initializer".to_string())),
+        };
+        let position = fields.iter()
+          .position(|f| *f == field)
+          .ok_or(AsaErrorKind::UnknownField { type_name: type_name.clone(), field: field.clone() })?;
+        if values[position].is_some() {
+          return Err(AsaErrorKind::Generic(format!("Field '{}' initialized more than once", field)));
+        }
+        values[position] = Some(value!(self.eval(&children[1])));
+      }
+    }
+    let mut entries = vec![];
+    for (field, value) in fields.into_iter().zip(values) {
+      match value {
+        Some(value) => entries.push((field, value)),
+        None => return Err(AsaErrorKind::MissingField { type_name, field }),
+      }
+    }
+    Ok(Flow::Normal(Value::new_struct(&type_name, entries)))
+  }
+
+  fn define_enum(&mut self, name: &[u8], children: &[Node]) -> Result<Value, AsaErrorKind> {
+    let enum_name = String::from_utf8_lossy(name).to_string();
+    for variant in children {
+      if let Node::EnumVariant {name, children} = variant {
+        let variant_name = String::from_utf8_lossy(name).to_string();
+        if self.variants.contains_key(&variant_name) {
+          return Err(AsaErrorKind::Generic(format!("Variant '{}' redefined", variant_name)));
+        }
+        let fields = children.iter().filter_map(|field| match field {
+          Node::Identifier {value, .. } => Some(String::from_utf8_lossy(value).to_string()),
+          _ => None,
+        }).collect();
+        self.variants.insert(variant_name, (enum_name.clone(), fields));
+      }
+    }
+    Ok(Value::Nil)
+  }
+
+  // The names left as identifiers by the resolver are enum variants without fields, named functions and
+  // undefined variables in the body of a try
+  fn exec_identifier(&mut self, value: &[u8], span: Span) -> Result<Value, AsaErrorKind> {
+    let name = std::str::from_utf8(value).unwrap();
+    match self.variants.get(name) {
+      Some((enum_name, fields)) if fields.is_empty() => {
+        Ok(Value::Variant(enum_name.clone(), name.to_string(), vec![]))
+      }
+      // Named functions can be used as values too
+      _ => self.functions.get(name)
+        .map(|function| Value::Function(function.clone()))
+        .ok_or(AsaErrorKind::VariableNotDefined { name: name.to_string(), span }),
+    }
+  }
+
+  fn exec_assignment(&mut self, children: &[Node]) -> Result<Flow, AsaErrorKind> {
+    let value = value!(self.eval(&children[1]));
+    match &children[0] {
+      Node::Index {children: target} => {
+        let collection = value!(self.eval(&target[0]));
+        let index = value!(self.eval(&target[1]));
+        set_index(&collection, &index, value)?;
+      }
+      Node::FieldAccess {name, children: target} => {
+        let instance = value!(self.eval(&target[0]));
+        set_field(&instance, &String::from_utf8_lossy(name), value)?;
+      }
+      _ => return Err(AsaErrorKind::Generic("Invalid assignment target".to_string())),
+    }
+    Ok(Flow::Normal(Value::Nil))
+  }
+  // Runs the top level items of a program or module. Imports and definitions are processed before any code runs,
+  // so functions can call functions defined later in the file. Then all calls and types are checked and the variables
+  // are resolved. The functions are replaced by their resolved versions, the constants are evaluated and the remaining
+  // top level items run in order. Variables and constants defined at the top level are globals.
+  fn exec_items(&mut self, tree: &Node) -> Result<Value, AsaErrorKind> {
+    for n in tree.children() {
+      if let Node::Import {..} | Node::FunctionDefine {..} | Node::StructDefine {..} | Node::EnumDefine {..} = n {
+        self.exec(n)?;
+      }
+    }
+    check_calls(tree)?;
+    check_types(tree)?;
+    let tree = resolve(tree, &mut self.global_names, &|name| self.functions.contains_key(name),
+      &|name| self.variants.get(name).is_some_and(|(_, fields)| fields.is_empty()),
+      &|name| self.functions.contains_key(name) || self.variants.contains_key(name) || builtin(name).is_some())?;
+    let children = tree.children();
+    for n in children {
+      if let Node::FunctionDefine {name, children} = n {
+        self.install_function(name, children);
+      }
+    }
+    self.define_constants(children)?;
+    let mut result = Value::Nil;
+    for n in children {
+      result = match n {
+        Node::Import {..} | Node::FunctionDefine {..} | Node::StructDefine {..} | Node::EnumDefine {..} | Node::ConstDefine {..} => Value::Nil,
+        _ => self.exec(n)?,
+      };
+    }
+    Ok(result)
+  }
+
+  // Evaluates the top level constants. A constant can refer to constants defined after it, so every constant is
+  // evaluated after the constants its initializer refers to.
+  fn define_constants(&mut self, items: &[Node]) -> Result<(), AsaErrorKind> {
+    let mut names = vec![];
+    let mut constants = HashMap::new();
+    for item in items {
+      if let Node::ConstDefine {children} = item {
+        let name = match &children[0] {
+          Node::Variable {name, ..} => String::from_utf8_lossy(name).to_string(),
+          _ => return Err(AsaErrorKind::Generic("Expected a resolved variable".to_string())),
+        };
+        names.push(name.clone());
+        constants.insert(name, (&children[1], binding(&children[0])?));
+      }
+    }
+    let mut dependencies = HashMap::new();
+    for name in &names {
+      let refers_to = constant_dependencies(name, constants[name].0, &|name| constants.contains_key(name))?;
+      dependencies.insert(name.clone(), refers_to);
+    }
+    let mut defined = HashSet::new();
+    for name in names {
+      self.define_constant(&name, &constants, &dependencies, &mut vec![], &mut defined)?;
+    }
+    Ok(())
+  }
+
+  // Evaluates a constant after its dependencies. The constants being evaluated are kept in order, so a constant
+  // that depends on itself is reported with the cycle that leads back to it.
+  fn define_constant(&mut self, name: &str, constants: &HashMap<String, (&Node, Binding)>, dependencies: &HashMap<String, Vec<String>>,
+                     evaluating: &mut Vec<String>, defined: &mut HashSet<String>) -> Result<(), AsaErrorKind> {
+    if defined.contains(name) {
+      return Ok(());
+    }
+    if let Some(start) = evaluating.iter().position(|constant| constant == name) {
+      let mut cycle = evaluating[start..].to_vec();
+      cycle.push(name.to_string());
+      return Err(AsaErrorKind::ConstantCycle(cycle));
+    }
+    evaluating.push(name.to_string());
+    for dependency in &dependencies[name] {
+      self.define_constant(dependency, constants, dependencies, evaluating, defined)?;
+    }
+    evaluating.pop();
+    let (initializer, binding) = constants[name];
+    let value = self.exec(initializer)?;
+    self.define_variable(binding, value)?;
+    defined.insert(name.to_string());
+    Ok(())
+  }
+
+  // Loads, parses and runs a module the first time it is imported. Later imports share the loaded module.
+  fn load_module(&mut self, path: &str) -> Result<Rc<Module>, AsaErrorKind> {
+    if let Some(module) = self.loaded.get(path) {
+      return Ok(module.clone());
+    }
+    // The importers of the current module and the current module itself are still loading
+    if let Some(start) = self.loading.iter().chain([&self.module]).position(|importer| importer == path) {
+      let mut cycle: Vec<String> = self.loading.iter().chain([&self.module]).skip(start).cloned().collect();
+      cycle.push(path.to_string());
+      return Err(AsaErrorKind::ImportCycle(cycle));
+    }
+    let source = self.loader.load(path)?;
+    let tree = match program(lex(&source)) {
+      Ok((tokens, tree)) if tokens.is_done() => tree,
+      _ => return Err(AsaErrorKind::Generic(format!("Failed to parse module '{}'", path))),
+    };
+
+    self.loading.push(self.module.clone());
+    let importer = self.switch_module(path);
+    let result = self.exec_items(&tree);
+    let mut members: HashMap<String, Value> = self.functions.iter()
+      .map(|(name, function)| (name.clone(), Value::Function(function.clone())))
+      .collect();
+    members.extend(self.global_names.iter().zip(&self.globals)
+      .filter_map(|(name, value)| Some((name.clone(), value.clone()?))));
+    self.switch_module(&importer);
+    self.loading.pop();
+    result?;
+
+    let module = Rc::new(Module { path: path.to_string(), members });
+    self.loaded.insert(path.to_string(), module.clone());
+    Ok(module)
+  }
+
+  // Makes the module with the given path the running one by swapping its definitions and globals into the interpreter.
+  // The state of the module that was running goes into the module table until it runs again. Returns the path of that module.
+  fn switch_module(&mut self, path: &str) -> String {
+    let mut state = self.modules.remove(path).unwrap_or_default();
+    std::mem::swap(&mut self.functions, &mut state.functions);
+    std::mem::swap(&mut self.structs, &mut state.structs);
+    std::mem::swap(&mut self.variants, &mut state.variants);
+    std::mem::swap(&mut self.globals, &mut state.globals);
+    std::mem::swap(&mut self.global_names, &mut state.global_names);
+    let previous = std::mem::replace(&mut self.module, path.to_string());
+    self.modules.insert(previous.clone(), state);
+    previous
+  }
+
+  // Evaluates the call arguments in the scope of the caller. The children are the children of a call node,
+  // the first of them is the FunctionArguments node.
+  fn exec_arguments(&mut self, children: &[Node]) -> Result<Flow<Arguments>, AsaErrorKind> {
+    let mut args = Arguments::default();
+    if let Some(Node::FunctionArguments { children }) = children.first() {
+      for arg in children {
+        match arg {
+          Node::NamedArgument { name, children, .. } => {
+            let value = value!(self.eval(&children[0]));
+            args.named.push((String::from_utf8_lossy(name).to_string(), value));
+          },
+          // The elements of a spread list are positional arguments
+          Node::Spread { children } => match value!(self.eval(&children[0])) {
+            Value::List(items) => args.positional.extend(items.borrow().iter().cloned()),
+            _ => return Err(AsaErrorKind::Generic("Only lists can be spread into arguments".to_string())),
+          },
+          _ => args.positional.push(value!(self.eval(arg))),
+        }
+      }
+    }
+    Ok(Flow::Normal(args))
+  }
+
+  fn call_value(&mut self, callee: Value, args: Arguments) -> Result<Value, AsaErrorKind> {
+    match callee {
+      Value::Function(function) => self.call_function(&function, args),
+      _ => Err(AsaErrorKind::Generic("Called value is not a function".to_string())),
+    }
+  }
+
+  // Calls a function with already evaluated arguments. A function that returns a call is replaced by the called
+  // function, which runs in the same place on the Rust stack.
+  fn call_function(&mut self, function: &Rc<Function>, args: Arguments) -> Result<Value, AsaErrorKind> {
+    let mut outcome = self.run_function(function, args)?;
+    loop {
+      outcome = match outcome {
+        Flow::Normal(value) | Flow::Return(value) => return Ok(value),
+        Flow::TailCall(function, args) => self.run_function(&function, args)?,
+      };
+    }
+  }
+
+  // Runs the body of a function in a new stack frame. A named function is in the first slot of its frame,
+  // so it can call itself, the arguments follow.
+  fn run_function(&mut self, function: &Rc<Function>, args: Arguments) -> Result<Flow, AsaErrorKind> {
+    let func_name = function.name.as_deref().unwrap_or("<lambda>");
+    if self.calls.len() >= self.max_depth {
+      let mut chain = self.calls.clone();
+      chain.push(func_name.to_string());
+      return Err(AsaErrorKind::StackOverflow { function: func_name.to_string(), chain });
+    }
+    self.calls.push(func_name.to_string());
+
+    // Push new stack frame
+    let mut slots = vec![];
+    if function.name.is_some() {
+      slots.push(Value::Function(function.clone()));
+    }
+    self.stack.push(Frame { slots, function: Some(function.clone()) });
+    // A function of another module runs with the definitions and globals of its module
+    let caller = if function.module != self.module {
+      Some(self.switch_module(&function.module))
+    } else {
+      None
+    };
+
+    // Bind the arguments, then execute the body
+    let final_result = match self.bind_arguments(function, args) {
+      Ok(()) => self.eval(&function.statements),
+      Err(e) => Err(e),
+    };
+
+    // Pop the stack frame
+    if let Some(caller) = caller {
+      self.switch_module(&caller);
+    }
+    self.stack.pop();
+    self.calls.pop();
+    final_result
+  }
+
+  // Binds the parameters of a function in the new stack frame. Every parameter takes the positional argument
+  // at its position, or the named argument with its name, or else its default value. Default values are
+  // evaluated at every call in the frame of the function, so they can refer to the parameters before them.
+  // A rest parameter takes the remaining positional arguments as a list. The parameters are in the slots after the function.
+  fn bind_arguments(&mut self, function: &Function, mut args: Arguments) -> Result<(), AsaErrorKind> {
+    let first_slot = usize::from(function.name.is_some());
+    let func_name = function.name.as_deref().unwrap_or("<lambda>");
+    let mut parameters = function.arguments.children();
+    let rest = match parameters.last() {
+      Some(Node::Spread { children }) => {
+        parameters = &parameters[..parameters.len() - 1];
+        Some((parameter_name(children)?, args.positional.split_off(parameters.len().min(args.positional.len()))))
+      },
+      _ => None,
+    };
+    if args.positional.len() > parameters.len() {
+      return Err(AsaErrorKind::Generic(format!(
+        "Function '{}' called with too many arguments. Expected at most {}, got {}",
+        func_name, parameters.len(), args.positional.len()
+      )));
+    }
+
+    let names: Vec<String> = parameters.iter()
+      .map(|parameter| parameter_name(parameter.children()))
+      .collect::<Result<_, _>>()?;
+    let mut values: Vec<Option<Value>> = args.positional.into_iter().map(Some).collect();
+    values.resize(parameters.len(), None);
+    for (argument, value) in args.named {
+      if rest.as_ref().is_some_and(|(name, _)| *name == argument) {
+        return Err(AsaErrorKind::RestArgumentByName { function: func_name.to_string(), argument });
+      }
+      let index = names.iter().position(|name| *name == argument)
+        .ok_or(AsaErrorKind::UnknownArgument { function: func_name.to_string(), argument: argument.clone() })?;
+      if values[index].is_some() {
+        return Err(AsaErrorKind::DuplicateArgument { function: func_name.to_string(), argument });
+      }
+      values[index] = Some(value);
+    }
+
+    let rest_slot = first_slot + parameters.len();
+    for (slot, ((parameter, name), value)) in (first_slot..).zip(parameters.iter().zip(names).zip(values)) {
+      let value = match (value, parameter.children().last()) {
+        (Some(value), _) => value,
+        (None, Some(default @ Node::Expression { .. })) => self.exec(default)?,
+        (None, _) => return Err(AsaErrorKind::MissingArgument { function: func_name.to_string(), argument: name }),
+      };
+      self.define_variable(Binding::Local(slot), value)?;
+    }
+    if let Some((_, values)) = rest {
+      self.define_variable(Binding::Local(rest_slot), Value::list(values))?;
+    }
+    Ok(())
+  }
+
+  // Helper function to unwrap the expression node of a conditional operand and evaluate it
+  fn resolve_conditional_operand(&mut self, node: &Node) -> Result<Flow, AsaErrorKind> {
+    match node {
+      Node::Expression { children } if !children.is_empty() => self.eval(&children[0]),
+      _ => Err(AsaErrorKind::Generic("Expected an Expression node as child of ConditionalExpression".to_string())),
+    }
+  }
+
   pub fn start_main(&mut self, arguments: Vec<Node>) -> Result<Value,AsaErrorKind> {
     // This node is equivalent to the following Asa program source code:
     // "main()"
     // It calls the main function with a FunctionArguments node as input.
-    let start_main = Node::FunctionCall{name: "main".into(), children: arguments};
+    let start_main = Node::FunctionCall{name: "main".into(), children: arguments, span: Span::default()};
     // Call the main function by running this code through the interpreter. 
     self.exec(&start_main)
   }
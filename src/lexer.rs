@@ -12,6 +12,12 @@ pub struct Token {
   pub end_col: u32,
 }
 
+impl Default for Token {
+  fn default() -> Self {
+    Token::new()
+  }
+}
+
 impl Token {
   pub fn new() -> Token {
     Token{
@@ -32,6 +38,40 @@ impl Token {
     self.kind = new_kind;
   }
 
+  pub fn span(&self) -> Span {
+    Span {
+      start_line: self.start_line,
+      start_col: self.start_col,
+      end_line: self.end_line,
+      end_col: self.end_col,
+    }
+  }
+
+}
+
+// Location of a piece of source code, used to point at the code an error is about.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Span {
+  pub start_line: u32,
+  pub start_col: u32,
+  pub end_line: u32,
+  pub end_col: u32,
+}
+
+impl Span {
+  pub fn new(start_line: u32, start_col: u32, end_line: u32, end_col: u32) -> Span {
+    Span { start_line, start_col, end_line, end_col }
+  }
+
+  // Span from the start of this span to the end of the other one
+  pub fn to(&self, other: Span) -> Span {
+    Span {
+      start_line: self.start_line,
+      start_col: self.start_col,
+      end_line: other.end_line,
+      end_col: other.end_col,
+    }
+  }
 }
 
 
@@ -43,6 +83,18 @@ pub enum TokenKind {
   Fn,
   Return,
   Let,
+  Struct,
+  Enum,
+  Match,
+  If,
+  Import,
+  As,
+  Const,
+  Try,
+  Catch,
+  Finally,
+  Throw,
+  Nil,
   //------
   Alpha,
   Digit,
@@ -50,12 +102,17 @@ pub enum TokenKind {
   RightParen,
   LeftCurly,
   RightCurly,
+  LeftBracket,
+  RightBracket,
   Equal,
   Plus,
   Dash,
+  Underscore,
   Quote,
   WhiteSpace,
   Semicolon,
+  Colon,
+  Dot,
   Comma,
   Slash,
   Other,
@@ -67,6 +124,10 @@ pub enum TokenKind {
   LessThanOrEqualTo, // <=
   EqualTo, // ==
   NotEqualTo, // !=
+  FatArrow, // =>
+  Arrow, // ->
+  Ellipsis, // ...
+  Asterisk, // *
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -74,6 +135,12 @@ pub struct Tokens {
     pub tokens: Vec<Token>,
 }
 
+impl Default for Tokens {
+    fn default() -> Self {
+        Tokens::new()
+    }
+}
+
 impl Tokens {
     pub fn new() -> Tokens {
         Tokens { tokens: vec![] }
@@ -93,10 +160,7 @@ impl Tokens {
 
     pub fn is_done(&self) -> bool {
         if !self.is_empty() {
-            match &self.tokens[0].kind {
-                TokenKind::EOF => true,
-                _ => false,
-            }
+            matches!(&self.tokens[0].kind, TokenKind::EOF)
         } else {
             true
         }
@@ -147,6 +211,48 @@ pub fn check_token(pred: &dyn Fn(&Token) -> bool) -> impl Fn(Tokens) -> IResult<
   }
 }
 
+// Keywords and the token kind they are lexed as
+const KEYWORDS: [(&[u8], TokenKind); 17] = [
+  (b"fn", TokenKind::Fn),
+  (b"true", TokenKind::True),
+  (b"false", TokenKind::False),
+  (b"let", TokenKind::Let),
+  (b"return", TokenKind::Return),
+  (b"struct", TokenKind::Struct),
+  (b"enum", TokenKind::Enum),
+  (b"match", TokenKind::Match),
+  (b"if", TokenKind::If),
+  (b"import", TokenKind::Import),
+  (b"as", TokenKind::As),
+  (b"const", TokenKind::Const),
+  (b"try", TokenKind::Try),
+  (b"catch", TokenKind::Catch),
+  (b"finally", TokenKind::Finally),
+  (b"throw", TokenKind::Throw),
+  (b"nil", TokenKind::Nil),
+];
+
+fn is_identifier_byte(c: u8) -> bool {
+  c.is_ascii_alphanumeric() || c == b'_'
+}
+
+// Checks whether the keyword starts at position i. The keyword has to be a whole word,
+// so identifiers that merely contain a keyword (e.g. construct) are not split up.
+fn matches_keyword(list: &[u8], i: usize, keyword: &[u8]) -> bool {
+  let end = i + keyword.len();
+  list[i..].starts_with(keyword)
+    && (i == 0 || !is_identifier_byte(list[i - 1]))
+    && (end == list.len() || !is_identifier_byte(list[end]))
+}
+
+// Lexing state for string literals. Whitespace inside a string is kept, and the code inside
+// the interpolation braces of a string is lexed like any other code.
+enum Mode {
+  // Counts the curly brackets opened by the code itself, so the closing bracket of an interpolation can be found
+  Code(u32),
+  String,
+}
+
 pub fn lex(input: &str) -> Tokens {
   let mut tokens = Tokens::new();
   let list = input.as_bytes();
@@ -154,17 +260,30 @@ pub fn lex(input: &str) -> Tokens {
   let mut line = 1;
   let mut col = 1;
   let mut diff = 0;
+  let mut modes = vec![Mode::Code(0)];
   while i < list.len() {
     let c = list[i];
+    let in_string = matches!(modes.last(), Some(Mode::String));
     let mut kind = match c {
+        // {{ and }} in a string are literal curly brackets
+        123 | 125 if in_string && i + 1 < list.len() && list[i + 1] == c => {
+          i += 1;
+          diff = 1;
+          TokenKind::Other
+        },
         48..=57 => TokenKind::Digit,
         65..=90 | 97..=122 => TokenKind::Alpha,
         32 | 10 | 9=> TokenKind::WhiteSpace,
-        // = and ==
+        // =, == and =>
         61 => {
           if i + 1 < list.len() && list[i + 1] == b'=' {
             i += 1;
+            diff = 1;
             TokenKind::EqualTo
+          } else if i + 1 < list.len() && list[i + 1] == b'>' {
+            i += 1;
+            diff = 1;
+            TokenKind::FatArrow
           } else {
             TokenKind::Equal
           }
@@ -173,6 +292,7 @@ pub fn lex(input: &str) -> Tokens {
         60 => {  
           if i + 1 < list.len() && list[i + 1] == b'=' {
             i += 1;
+            diff = 1;
             TokenKind::LessThanOrEqualTo
           } else {
             TokenKind::LessThan
@@ -182,6 +302,7 @@ pub fn lex(input: &str) -> Tokens {
         62 => {  
           if i + 1 < list.len() && list[i + 1] == b'=' {
             i += 1;
+            diff = 1;
             TokenKind::GreaterThanOrEqualTo
           } else {
             TokenKind::GreaterThan
@@ -191,86 +312,96 @@ pub fn lex(input: &str) -> Tokens {
         33 => {  
           if i + 1 < list.len() && list[i + 1] == b'=' {
             i += 1;
+            diff = 1;
             TokenKind::NotEqualTo
           } else {
             TokenKind::Other  
           }
         },
         59 => TokenKind::Semicolon,
+        58 => TokenKind::Colon,
+        // . and ...
+        46 => {
+          if i + 2 < list.len() && list[i + 1] == b'.' && list[i + 2] == b'.' {
+            i += 2;
+            diff = 2;
+            TokenKind::Ellipsis
+          } else {
+            TokenKind::Dot
+          }
+        },
         123 => TokenKind::LeftCurly,
         125 => TokenKind::RightCurly,
+        91 => TokenKind::LeftBracket,
+        93 => TokenKind::RightBracket,
         40 => TokenKind::LeftParen,
         41 => TokenKind::RightParen,
         43 => TokenKind::Plus,
-        45 => TokenKind::Dash,
-        44 => TokenKind::Comma,
-        34 => TokenKind::Quote,
-        x => TokenKind::Other,
-    };
-    //check if fn
-    if c == b'f' {
-        if i + 1 < list.len(){
-          if list[i + 1] == b'n' {
-            kind = TokenKind::Fn;
+        42 => TokenKind::Asterisk,
+        47 => TokenKind::Slash,
+        // - and ->
+        45 => {
+          if i + 1 < list.len() && list[i + 1] == b'>' {
             i += 1;
             diff = 1;
+            TokenKind::Arrow
+          } else {
+            TokenKind::Dash
           }
-        }
-    }
-    //check if true
-    if c == b't' {
-      if i + 3 < list.len(){
-        if list[i + 1] == b'r' && list[i + 2] == b'u' && list[i + 3] == b'e' {
-          kind = TokenKind::True;
-          i += 3;
-          diff = 3;
-        }
-      }
-    }
-    //check if false
-    if c == b'f' {
-      if i + 4 < list.len(){
-        if list[i + 1] == b'a' && list[i + 2] == b'l' && list[i + 3] == b's' && list[i + 4] == b'e' {
-          kind = TokenKind::False;
-          i += 4;
-          diff = 4;
-        }
-      }
+        },
+        95 => TokenKind::Underscore,
+        44 => TokenKind::Comma,
+        34 => TokenKind::Quote,
+        _ => TokenKind::Other,
+    };
+    // The lexeme covers all characters of the token, except for {{ and }} in strings, which stand for one curly bracket
+    let mut lexeme = if matches!(c, b'{' | b'}') { vec![c] } else { list[i - diff as usize..=i].to_vec() };
+    //check if keyword
+    if let Some((keyword, keyword_kind)) = KEYWORDS.iter().find(|(keyword, _)| matches_keyword(list, i, keyword)) {
+      kind = *keyword_kind;
+      lexeme = keyword.to_vec();
+      i += keyword.len() - 1;
+      diff = keyword.len() as u32 - 1;
     }
-    //check if let
-    if c == b'l' { 
-      if i + 2 < list.len(){
-        if list[i + 1] == b'e' && list[i + 2] == b't' {
-          kind = TokenKind::Let;
-          i += 2;
-          diff = 2;
+
+    // Quotes and curly brackets switch between strings and code
+    match kind {
+      TokenKind::Quote if in_string => { modes.pop(); },
+      TokenKind::Quote => modes.push(Mode::String),
+      TokenKind::LeftCurly if in_string => modes.push(Mode::Code(0)),
+      TokenKind::LeftCurly => {
+        if let Some(Mode::Code(depth)) = modes.last_mut() {
+          *depth += 1;
         }
-      }
-    }
-    //check if return
-    if c == b'r' {
-      if i + 5 < list.len(){
-        if list[i + 1] == b'e' && list[i + 2] == b't' && list[i + 3] == b'u' && list[i + 4] == b'r' && list[i + 5] == b'n' {
-          kind = TokenKind::Return;
-          i += 5;
-          diff = 5;
+      },
+      TokenKind::RightCurly if !in_string => {
+        let nested = modes.len() > 1;
+        match modes.last_mut() {
+          // Closes the interpolation, back to the string
+          Some(Mode::Code(0)) if nested => { modes.pop(); },
+          Some(Mode::Code(depth)) => *depth = depth.saturating_sub(1),
+          _ => {},
         }
-      }
+      },
+      _ => {},
     }
 
     //create token struct
     let token = Token {
         kind,
-        lexeme: vec![c],
+        lexeme,
         start_col: col,
         end_col: col + diff,
         start_line: line,
         end_line: line,
     };
     i +=1;
+    // Whitespace only matters inside strings
+    if kind != TokenKind::WhiteSpace || in_string {
+      tokens.push(token.clone());
+    }
+    col += 1 + diff;
     diff = 0;
-    tokens.push(token.clone());
-    col +=1;
     if c == 10{
       line +=1;
       col = 1;
@@ -286,8 +417,5 @@ pub fn lex(input: &str) -> Tokens {
     end_line: line,
     };
   tokens.push(token);
-
-  
-  let filtered_tokens: Vec<Token> = tokens.tokens.iter().filter(|tkn| tkn.kind != TokenKind::WhiteSpace).cloned().collect();
-  Tokens::from(filtered_tokens)
+  tokens
 }
@@ -4,8 +4,20 @@ pub mod interpreter;
 pub mod parser;
 pub mod error;
 pub mod lexer;
+pub mod builtins;
+pub mod checker;
+pub mod modules;
+pub mod typechecker;
+pub mod resolver;
+pub mod bigint;
 
 pub use self::parser::*;
 pub use self::interpreter::*;
 pub use self::lexer::*;
-pub use self::error::*;
\ No newline at end of file
+pub use self::error::*;
+pub use self::builtins::*;
+pub use self::checker::*;
+pub use self::modules::*;
+pub use self::typechecker::*;
+pub use self::resolver::*;
+pub use self::bigint::*;
\ No newline at end of file
@@ -3,7 +3,14 @@ extern crate asalang;
 
 use asalang::*;
 
+// The interpreter runs on a thread with the stack its default limits need, see DEFAULT_STACK_SIZE
 fn main() -> Result<(), AsaErrorKind> {
+  let interpreter = std::thread::Builder::new().stack_size(DEFAULT_STACK_SIZE).spawn(run)
+    .map_err(|e| AsaErrorKind::Generic(e.to_string()))?;
+  interpreter.join().map_err(|_| AsaErrorKind::Generic("the interpreter thread panicked".to_string()))
+}
+
+fn run() {
   
   let tokens = lex("123");
   match program(tokens) {
@@ -16,7 +23,4 @@ fn main() -> Result<(), AsaErrorKind> {
     },
     Err(e) => println!("Error: {:?}", e),
   }
-
-    
-  Ok(())
 }
@@ -0,0 +1,87 @@
+// Loading of imported modules
+
+use crate::error::*;
+use crate::interpreter::Value;
+use std::collections::HashMap;
+
+// Supplies the source code of imported modules. The path is already resolved relative to the importing module.
+pub trait ModuleLoader: std::fmt::Debug {
+  fn load(&self, path: &str) -> Result<String, AsaErrorKind>;
+}
+
+// Loads modules from the file system. This is the loader of Interpreter::new().
+#[derive(Debug, Default)]
+pub struct FileLoader;
+
+impl ModuleLoader for FileLoader {
+  fn load(&self, path: &str) -> Result<String, AsaErrorKind> {
+    std::fs::read_to_string(path).map_err(|_| AsaErrorKind::ModuleNotFound(path.to_string()))
+  }
+}
+
+// Serves modules from memory, for tests and for hosts that do not keep their scripts in files.
+#[derive(Debug, Default)]
+pub struct MemoryLoader {
+  // Key - Module path
+  // Value - Source code
+  modules: HashMap<String, String>,
+}
+
+impl MemoryLoader {
+  pub fn new() -> MemoryLoader {
+    MemoryLoader { modules: HashMap::new() }
+  }
+
+  pub fn add(&mut self, path: &str, source: &str) {
+    self.modules.insert(path.to_string(), source.to_string());
+  }
+}
+
+impl ModuleLoader for MemoryLoader {
+  fn load(&self, path: &str) -> Result<String, AsaErrorKind> {
+    self.modules.get(path)
+      .cloned()
+      .ok_or(AsaErrorKind::ModuleNotFound(path.to_string()))
+  }
+}
+
+// A loaded module. Its top level functions and variables are the members of the namespace it is imported as.
+#[derive(Debug, PartialEq)]
+pub struct Module {
+  pub path: String,
+  pub members: HashMap<String, Value>,
+}
+
+// Resolves an import path relative to the directory of the importing module.
+// "." and ".." are normalized away, so every module is known by exactly one path.
+pub fn resolve_path(importer: &str, path: &str) -> String {
+  let mut parts: Vec<&str> = importer.split('/').collect();
+  // Drop the file name of the importer
+  parts.pop();
+  if path.starts_with('/') {
+    parts = vec![""];
+  }
+  for part in path.split('/') {
+    match part {
+      "" | "." => {},
+      ".." if matches!(parts.last(), Some(last) if !last.is_empty() && *last != "..") => {
+        parts.pop();
+      },
+      _ => parts.push(part),
+    }
+  }
+  parts.join("/")
+}
+
+// The namespace of a module imported without "as": the file name without its extension
+pub fn module_name(path: &str) -> Result<String, AsaErrorKind> {
+  let file = path.rsplit('/').next().unwrap_or(path);
+  let name = file.split('.').next().unwrap_or(file);
+  let valid = name.bytes().next().is_some_and(|c| c.is_ascii_alphabetic() || c == b'_')
+    && name.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'_');
+  if valid {
+    Ok(name.to_string())
+  } else {
+    Err(AsaErrorKind::Generic(format!("Module '{}' has to be imported with a name: import \"{}\" as name;", path, path)))
+  }
+}
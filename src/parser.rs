@@ -4,19 +4,18 @@
 
 //use nom::*;
 use crate::lexer::*;
-use nom::sequence::tuple;
-use nom::combinator::map;
-use nom::sequence::pair;
+use crate::resolver::Binding;
+use crate::bigint::BigInt;
+use nom::sequence::{pair, preceded};
 use nom::multi::fold_many0;
 
 
  use nom::{
   IResult,
   branch::alt,
-  combinator::opt,
-  multi::{many1, many0},
-  bytes::complete::{tag},
-  character::complete::{alphanumeric1, digit1},
+  combinator,
+  combinator::{opt, cut},
+  multi::{many1, many0, separated_list0, separated_list1},
 };
  
 // Here are the different node types. You will use these to make your parser.
@@ -30,51 +29,188 @@ pub enum Node {
   FunctionArguments { children: Vec<Node> },
   FunctionStatements { children: Vec<Node> },
   Expression { children: Vec<Node> },
-  MathExpression {name: Vec<u8>, children: Vec<Node> },
-  FunctionCall { name: Vec<u8>, children: Vec<Node> },
+  MathExpression {name: Vec<u8>, children: Vec<Node>, span: Span },
+  FunctionCall { name: Vec<u8>, children: Vec<Node>, span: Span },
   VariableDefine { children: Vec<Node> },
   FunctionReturn { children: Vec<Node> },
   Number { value: i32 },
+  // An integer literal with the suffix n, of any size
+  BigInt { value: BigInt },
+  // A number literal without the suffix n that does not fit in 32 bits. It is handled by the arithmetic mode of
+  // the interpreter like the result of an operator, at the span of the literal.
+  LargeNumber { value: BigInt, span: Span },
   Bool { value: bool },
-  Identifier { value: Vec<u8> },
+  Identifier { value: Vec<u8>, span: Span },
   String { value: String },
   Comment { value: Vec<u8> },
-  ConditionalExpression { name: Vec<u8>, children: Vec<Node> },
+  ConditionalExpression { name: Vec<u8>, children: Vec<Node>, span: Span },
+  Block { children: Vec<Node> },
+  List { children: Vec<Node> },
+  Index { children: Vec<Node> },
+  Assignment { children: Vec<Node> },
+  Map { children: Vec<Node> },
+  KeyValue { children: Vec<Node> },
+  StructDefine { name: Vec<u8>, children: Vec<Node> },
+  StructLiteral { name: Vec<u8>, children: Vec<Node> },
+  FieldAccess { name: Vec<u8>, children: Vec<Node> },
+  EnumDefine { name: Vec<u8>, children: Vec<Node> },
+  EnumVariant { name: Vec<u8>, children: Vec<Node> },
+  Match { children: Vec<Node>, span: Span },
+  MatchArm { children: Vec<Node> },
+  MatchGuard { children: Vec<Node> },
+  VariantPattern { name: Vec<u8>, children: Vec<Node> },
+  Wildcard,
+  Lambda { children: Vec<Node> },
+  Call { children: Vec<Node> },
+  Import { name: Vec<u8>, children: Vec<Node> },
+  TypeAnnotation { name: Vec<u8>, children: Vec<Node>, span: Span },
+  TypeParameters { children: Vec<Node> },
+  Interpolation { children: Vec<Node> },
+  // An argument passed by the name of the parameter, the child is the value
+  NamedArgument { name: Vec<u8>, children: Vec<Node>, span: Span },
+  ConstDefine { children: Vec<Node> },
+  Try { children: Vec<Node> },
+  Throw { children: Vec<Node> },
+  // A rest parameter (identifier and optional type annotation) or a spread argument (expression)
+  Spread { children: Vec<Node> },
   Null,
+  // Nodes added by the resolver, see resolver.rs
+  // A variable with the slot it is stored in
+  Variable { name: Vec<u8>, binding: Binding, span: Span },
+  // The variables of the surrounding function a lambda captures, in the order of its environment
+  Captures { children: Vec<Node> },
+}
+
+impl Node {
+  // The child nodes, for passes that walk the whole tree
+  pub fn children(&self) -> &[Node] {
+    match self {
+      Node::Program { children } |
+      Node::Statement { children } |
+      Node::FunctionDefine { children, .. } |
+      Node::FunctionArguments { children } |
+      Node::FunctionStatements { children } |
+      Node::Expression { children } |
+      Node::MathExpression { children, .. } |
+      Node::FunctionCall { children, .. } |
+      Node::VariableDefine { children } |
+      Node::FunctionReturn { children } |
+      Node::ConditionalExpression { children, .. } |
+      Node::Block { children } |
+      Node::List { children } |
+      Node::Index { children } |
+      Node::Assignment { children } |
+      Node::Map { children } |
+      Node::KeyValue { children } |
+      Node::StructDefine { children, .. } |
+      Node::StructLiteral { children, .. } |
+      Node::FieldAccess { children, .. } |
+      Node::EnumDefine { children, .. } |
+      Node::EnumVariant { children, .. } |
+      Node::Match { children, .. } |
+      Node::MatchArm { children } |
+      Node::MatchGuard { children } |
+      Node::VariantPattern { children, .. } |
+      Node::Lambda { children } |
+      Node::Call { children } |
+      Node::Import { children, .. } |
+      Node::TypeAnnotation { children, .. } |
+      Node::TypeParameters { children } |
+      Node::Interpolation { children } |
+      Node::NamedArgument { children, .. } |
+      Node::ConstDefine { children } |
+      Node::Try { children } |
+      Node::Throw { children } |
+      Node::Spread { children } |
+      Node::Captures { children } => children,
+      Node::Number { .. } |
+      Node::BigInt { .. } |
+      Node::LargeNumber { .. } |
+      Node::Bool { .. } |
+      Node::Identifier { .. } |
+      Node::String { .. } |
+      Node::Comment { .. } |
+      Node::Wildcard |
+      Node::Null |
+      Node::Variable { .. } => &[],
+    }
+  }
+
+  // The child nodes, for passes that rewrite the tree in place
+  pub fn children_mut(&mut self) -> &mut [Node] {
+    match self {
+      Node::Program { children } |
+      Node::Statement { children } |
+      Node::FunctionDefine { children, .. } |
+      Node::FunctionArguments { children } |
+      Node::FunctionStatements { children } |
+      Node::Expression { children } |
+      Node::MathExpression { children, .. } |
+      Node::FunctionCall { children, .. } |
+      Node::VariableDefine { children } |
+      Node::FunctionReturn { children } |
+      Node::ConditionalExpression { children, .. } |
+      Node::Block { children } |
+      Node::List { children } |
+      Node::Index { children } |
+      Node::Assignment { children } |
+      Node::Map { children } |
+      Node::KeyValue { children } |
+      Node::StructDefine { children, .. } |
+      Node::StructLiteral { children, .. } |
+      Node::FieldAccess { children, .. } |
+      Node::EnumDefine { children, .. } |
+      Node::EnumVariant { children, .. } |
+      Node::Match { children, .. } |
+      Node::MatchArm { children } |
+      Node::MatchGuard { children } |
+      Node::VariantPattern { children, .. } |
+      Node::Lambda { children } |
+      Node::Call { children } |
+      Node::Import { children, .. } |
+      Node::TypeAnnotation { children, .. } |
+      Node::TypeParameters { children } |
+      Node::Interpolation { children } |
+      Node::NamedArgument { children, .. } |
+      Node::ConstDefine { children } |
+      Node::Try { children } |
+      Node::Throw { children } |
+      Node::Spread { children } |
+      Node::Captures { children } => children,
+      Node::Number { .. } |
+      Node::BigInt { .. } |
+      Node::LargeNumber { .. } |
+      Node::Bool { .. } |
+      Node::Identifier { .. } |
+      Node::String { .. } |
+      Node::Comment { .. } |
+      Node::Wildcard |
+      Node::Null |
+      Node::Variable { .. } => &mut [],
+    }
+  }
 }
 
 // Some helper functions to use Tokens instead of a &str with Nom. 
 // You'll probably have to create more of these as needed.
 
 pub fn t_alpha(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::Alpha => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Alpha));
   fxn(input.clone())
 }
 
 pub fn t_digit(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::Digit => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Digit));
   fxn(input.clone())
 }
 
 pub fn t_true(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::True => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::True));
   fxn(input.clone())
 }
 
 pub fn t_false(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::False => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::False));
   fxn(input.clone())
 }
 
@@ -98,116 +234,186 @@ pub fn t_alphanumeric0(input: Tokens) -> IResult<Tokens, Vec<Token>> {
 // keywords 
 
 pub fn t_left_paren(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(&|tk| match tk.kind {
-    TokenKind::LeftParen => true,
-    _=> false,
-  }) ;
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::LeftParen));
   fxn(input.clone())
 }
 
 pub fn t_right_paren(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(&|tk| match tk.kind {
-    TokenKind::RightParen => true,
-    _=> false,
-  }) ;
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::RightParen));
   fxn(input.clone())
 }
 
 // Helper function to parse the curly brackets
 pub fn t_left_curly(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(&|tk| match tk.kind {
-    TokenKind::LeftCurly => true,
-    _=> false,
-  }) ;
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::LeftCurly));
   fxn(input.clone())
 }
 
 pub fn t_right_curly(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(&|tk| match tk.kind {
-    TokenKind::RightCurly => true,
-    _=> false,
-  }) ;
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::RightCurly));
+  fxn(input.clone())
+}
+
+// Helper function to parse the square brackets
+pub fn t_left_bracket(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::LeftBracket));
+  fxn(input.clone())
+}
+
+pub fn t_right_bracket(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::RightBracket));
   fxn(input.clone())
 }
 
 pub fn t_quote(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::Quote => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Quote));
   fxn(input.clone())
 }
 
 pub fn t_slash(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::Slash => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Slash));
   fxn(input.clone())
 }
 
 pub fn t_comma(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(&|tk| match tk.kind {
-    TokenKind::Comma => true,
-    _=> false,
-  }) ;
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Comma));
   fxn(input.clone())
 }
 
 pub fn t_semicolon(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(&|tk| match tk.kind {
-    TokenKind::Semicolon => true,
-    _=> false,
-  }) ;
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Semicolon));
+  fxn(input.clone())
+}
+
+pub fn t_colon(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Colon));
   fxn(input.clone())
 }
 
 
 pub fn t_let(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::Let => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Let));
   fxn(input.clone())
 }
 
 pub fn t_fn(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::Fn => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Fn));
   fxn(input.clone())
 }
 
 pub fn t_return(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::Return => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Return));
+  fxn(input.clone())
+}
+
+pub fn t_struct(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Struct));
+  fxn(input.clone())
+}
+
+pub fn t_enum(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Enum));
+  fxn(input.clone())
+}
+
+pub fn t_match(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Match));
+  fxn(input.clone())
+}
+
+pub fn t_if(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::If));
+  fxn(input.clone())
+}
+
+pub fn t_fat_arrow(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::FatArrow));
+  fxn(input.clone())
+}
+
+pub fn t_underscore(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Underscore));
+  fxn(input.clone())
+}
+
+pub fn t_dot(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Dot));
+  fxn(input.clone())
+}
+
+pub fn t_import(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Import));
+  fxn(input.clone())
+}
+
+pub fn t_as(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::As));
+  fxn(input.clone())
+}
+
+pub fn t_arrow(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Arrow));
+  fxn(input.clone())
+}
+
+pub fn t_ellipsis(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Ellipsis));
   fxn(input.clone())
 }
 
 pub fn t_whitespace(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::WhiteSpace => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::WhiteSpace));
+  fxn(input.clone())
+}
+
+pub fn t_asterisk(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Asterisk));
+  fxn(input.clone())
+}
+
+pub fn t_const(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Const));
+  fxn(input.clone())
+}
+
+pub fn t_try(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Try));
+  fxn(input.clone())
+}
+
+pub fn t_catch(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Catch));
+  fxn(input.clone())
+}
+
+pub fn t_finally(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Finally));
+  fxn(input.clone())
+}
+
+pub fn t_throw(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Throw));
+  fxn(input.clone())
+}
+
+pub fn t_nil(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Nil));
+  fxn(input.clone())
+}
+
+// The letter n after the digits of a big integer
+pub fn t_bigint_suffix(input: Tokens) -> IResult<Tokens, Token> {
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Alpha) && tk.lexeme == b"n");
   fxn(input.clone())
 }
 
 pub fn t_plus(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::Plus => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Plus));
   fxn(input.clone())
 }
 
 pub fn t_dash(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::Dash => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Dash));
   fxn(input.clone())
 }
 
@@ -215,82 +421,85 @@ pub fn t_dash(input: Tokens) -> IResult<Tokens, Token> {
 
 // == and =
 pub fn t_equal_to(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::EqualTo => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::EqualTo));
   fxn(input.clone())
 }
 
 pub fn t_equal(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::Equal => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::Equal));
   fxn(input.clone())
 }
 
 // !=
 pub fn t_not_equal_to(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::NotEqualTo => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::NotEqualTo));
   fxn(input.clone())
 }
 
 // <= and <
 pub fn t_less_than(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::LessThan => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::LessThan));
   fxn(input.clone())
 }
 
 pub fn t_less_than_or_equal_to(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::LessThanOrEqualTo => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::LessThanOrEqualTo));
   fxn(input.clone())
 }
 
 // >= and >
 pub fn t_greater_than(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::GreaterThan => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::GreaterThan));
   fxn(input.clone())
 }
 
 pub fn t_greater_than_or_equal_to(input: Tokens) -> IResult<Tokens, Token> {
-  let fxn = check_token(& |tk| match tk.kind {
-    TokenKind::GreaterThanOrEqualTo => true,
-    _ => false,
-  });
+  let fxn = check_token(&|tk| matches!(tk.kind, TokenKind::GreaterThanOrEqualTo));
   fxn(input.clone())
 }
 
+// Span of the tokens consumed between two states of the input
+pub fn span_between(start: &Tokens, end: &Tokens) -> Span {
+  let consumed = start.len() - end.len();
+  match (start.tokens.first(), start.tokens.get(consumed.saturating_sub(1))) {
+    (Some(first), Some(last)) => first.span().to(last.span()),
+    _ => Span::default(),
+  }
+}
+
 pub fn identifier(input: Tokens) -> IResult<Tokens, Node> {
-  let (input, first) = t_alpha(input)?;
-  let (input, rest) = t_alphanumeric0(input)?;
+  let start = input.clone();
+  let (input, first) = alt((t_alpha, t_underscore))(input)?;
+  let (input, rest) = many0(alt((t_alpha, t_digit, t_underscore)))(input)?;
   let mut identifier = first.lexeme;
   for mut tk in rest {
     identifier.append(&mut tk.lexeme);
   }
-  Ok((input,Node::Identifier{value: identifier}))
+  let span = span_between(&start, &input);
+  Ok((input,Node::Identifier{value: identifier, span}))
 }
 
+// number = digit , {digit} , ["n"] ;
+// A number with the suffix n is a big integer of any size, a number without it is a large number if it does not
+// fit in 32 bits.
 pub fn number(input: Tokens) -> IResult<Tokens, Node> {
+  let start = input.clone();
   let (input, digits) = many1(t_digit)(input)?;
   let value: Vec<u8> = digits.iter()
                              .flat_map(|token| token.lexeme.iter())
                              .cloned()
                              .collect();
-  let parsed_value: i32 = std::str::from_utf8(&value).unwrap().parse::<i32>().unwrap();
-  Ok((input, Node::Number { value: parsed_value }))
+  let value = std::str::from_utf8(&value).unwrap();
+  if let Ok((input, _)) = t_bigint_suffix(input.clone()) {
+    return Ok((input, Node::BigInt { value: value.parse().unwrap() }));
+  }
+  match value.parse::<i32>() {
+    Ok(parsed_value) => Ok((input, Node::Number { value: parsed_value })),
+    Err(_) => {
+      let span = span_between(&start, &input);
+      Ok((input, Node::LargeNumber { value: value.parse().unwrap(), span }))
+    }
+  }
 }
 
 pub fn boolean(input: Tokens) -> IResult<Tokens, Node> {
@@ -303,18 +512,47 @@ pub fn boolean(input: Tokens) -> IResult<Tokens, Node> {
   Ok((input, Node::Bool { value }))
 }
 
+// nil = "nil" ;
+pub fn nil(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_nil(input)?;
+  Ok((input, Node::Null))
+}
+
+// string = "\"" , {character | "{" , expression , "}"} , "\"" ;
+// A string without interpolations is a String node. Otherwise it is an Interpolation node whose children are
+// the String pieces and the expressions in order. Once the opening quote is read, a malformed string is a parse
+// failure instead of a backtrack, e.g. an unclosed or empty interpolation or a single closing curly bracket.
 pub fn string(input: Tokens) -> IResult<Tokens, Node> {
- let (input, _) = t_quote(input)?;
-  let (input, string) = t_alphanumeric0(input)?;
- let (input, _) = t_quote(input)?;
- let value: Vec<u8> = string.into_iter()
-                               .map(|token| token.lexeme)
-                               .flatten()
-                               .collect();
- Ok((input, Node::String{ value: String::from_utf8(value).unwrap() }))
+  let (input, _) = t_quote(input)?;
+  let (input, mut parts) = many0(alt((string_text, interpolation)))(input)?;
+  let (input, _) = cut(t_quote)(input)?;
+  let node = match parts.len() {
+    0 => Node::String{ value: String::new() },
+    1 if matches!(parts[0], Node::String{..}) => parts.remove(0),
+    _ => Node::Interpolation{ children: parts },
+  };
+  Ok((input, node))
+}
+
+// The literal text of a string. The lexer keeps whitespace inside strings and turns {{ and }} into literal curly brackets.
+fn string_text(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, text) = many1(check_token(&|tk| !matches!(tk.kind, TokenKind::Quote | TokenKind::LeftCurly | TokenKind::RightCurly | TokenKind::EOF)))(input)?;
+  let value: Vec<u8> = text.into_iter()
+                           .flat_map(|token| token.lexeme)
+                           .collect();
+  Ok((input, Node::String{ value: String::from_utf8(value).unwrap() }))
+}
+
+// "{" , expression , "}"
+fn interpolation(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_left_curly(input)?;
+  let (input, expression) = cut(expression)(input)?;
+  let (input, _) = cut(t_right_curly)(input)?;
+  Ok((input, expression))
 }
 
 pub fn function_call(input: Tokens) -> IResult<Tokens, Node> {
+  let start = input.clone();
   let (input, fxn_name) = identifier(input)?;
   let (input, _) = (t_left_paren)(input)?;
   let (input, args) = many0(arguments)(input)?;
@@ -325,26 +563,271 @@ pub fn function_call(input: Tokens) -> IResult<Tokens, Node> {
     args
   };
   let name: Vec<u8> = match fxn_name {
-    Node::Identifier{value} => value,
+    Node::Identifier{value, ..} => value,
     _ => unreachable!(),
   }; 
-  Ok((input, Node::FunctionCall{name, children: args}))
+  let span = span_between(&start, &input);
+  Ok((input, Node::FunctionCall{name, children: args, span}))
 }
 
 pub fn value(input: Tokens) -> IResult<Tokens, Node> {
-  alt((number, identifier, boolean))(input)
+  postfix_expression(input)
+}
+
+// primary = boolean | nil | match_expression | try_expression | lambda | function_call | number | string | struct_literal | identifier | map | block | list ;
+// map comes before block: a map starts with a string key and a colon, which a block never does.
+// Empty braces in expression position are an empty map.
+pub fn primary(input: Tokens) -> IResult<Tokens, Node> {
+  alt((boolean, nil, match_expression, try_expression, lambda, function_call, number, string, struct_literal, identifier, map, block, list))(input)
+}
+
+// lambda = "fn" , "(" , [parameters] , ")" , block ;
+pub fn lambda(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_fn(input)?;
+  let (input, _) = t_left_paren(input)?;
+  let (input, fxn_arguments) = parameters(input)?;
+  let (input, _) = t_right_paren(input)?;
+  let (input, statements) = block_body(input)?;
+  let fxn_statements = Node::FunctionStatements{children: statements};
+  Ok((input, Node::Lambda{children: vec![fxn_arguments, fxn_statements]}))
+}
+
+// map = "{" , [key_value , {"," , key_value}] , "}" ;
+pub fn map(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_left_curly(input)?;
+  let (input, children) = separated_list0(t_comma, key_value)(input)?;
+  let (input, _) = t_right_curly(input)?;
+  Ok((input, Node::Map{children}))
+}
+
+// key_value = string , ":" , expression ;
+pub fn key_value(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, key) = string(input)?;
+  let (input, _) = t_colon(input)?;
+  let (input, value) = expression(input)?;
+  Ok((input, Node::KeyValue{children: vec![key, value]}))
+}
+
+// list = "[" , [expression , {"," , expression}] , "]" ;
+pub fn list(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_left_bracket(input)?;
+  let (input, children) = separated_list0(t_comma, expression)(input)?;
+  let (input, _) = t_right_bracket(input)?;
+  Ok((input, Node::List{children}))
+}
+
+// postfix_expression = primary , {("[" , expression , "]") | ("." , identifier) | ("(" , [arguments] , ")")} ;
+pub fn postfix_expression(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, target) = primary(input)?;
+  fold_many0(
+    // Each suffix is parsed into a node without its target,
+    // the expression parsed so far is added as the first child.
+    alt((index_suffix, field_suffix, call_suffix)), move || target.clone(), |acc, suffix| {
+      match suffix {
+        Node::Index{mut children} => {
+          children.insert(0, acc);
+          Node::Index{children}
+        },
+        Node::Call{mut children} => {
+          children.insert(0, acc);
+          Node::Call{children}
+        },
+        Node::FieldAccess{name, ..} => Node::FieldAccess{name, children: vec![acc]},
+        _ => unreachable!(),
+      }
+    }
+  )(input)
+}
+
+fn index_suffix(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_left_bracket(input)?;
+  let (input, index) = expression(input)?;
+  let (input, _) = t_right_bracket(input)?;
+  Ok((input, Node::Index{children: vec![index]}))
 }
 
+// Calls the value of the expression before it, e.g. make_adder(1)(2)
+fn call_suffix(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_left_paren(input)?;
+  let (input, args) = opt(arguments)(input)?;
+  let (input, _) = t_right_paren(input)?;
+  let args = args.unwrap_or(Node::FunctionArguments{children: vec![]});
+  Ok((input, Node::Call{children: vec![args]}))
+}
+
+fn field_suffix(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_dot(input)?;
+  let (input, field) = identifier(input)?;
+  let name = match field {
+    Node::Identifier{value, ..} => value,
+    _ => unreachable!(),
+  };
+  Ok((input, Node::FieldAccess{name, children: vec![]}))
+}
+
+// struct_define = "struct" , identifier , "{" , [identifier , {"," , identifier}] , "}" ;
+pub fn struct_define(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_struct(input)?;
+  let (input, struct_name) = identifier(input)?;
+  let name = match struct_name {
+    Node::Identifier{value, ..} => value,
+    _ => unreachable!(),
+  };
+  let (input, _) = t_left_curly(input)?;
+  let (input, fields) = separated_list0(t_comma, identifier)(input)?;
+  let (input, _) = t_right_curly(input)?;
+  Ok((input, Node::StructDefine{name, children: fields}))
+}
+
+// enum_define = "enum" , identifier , "{" , [enum_variant , {"," , enum_variant}] , "}" ;
+pub fn enum_define(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_enum(input)?;
+  let (input, enum_name) = identifier(input)?;
+  let name = match enum_name {
+    Node::Identifier{value, ..} => value,
+    _ => unreachable!(),
+  };
+  let (input, _) = t_left_curly(input)?;
+  let (input, variants) = separated_list0(t_comma, enum_variant)(input)?;
+  let (input, _) = t_right_curly(input)?;
+  Ok((input, Node::EnumDefine{name, children: variants}))
+}
+
+// enum_variant = identifier , ["(" , identifier , {"," , identifier} , ")"] ;
+pub fn enum_variant(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, variant_name) = identifier(input)?;
+  let name = match variant_name {
+    Node::Identifier{value, ..} => value,
+    _ => unreachable!(),
+  };
+  let (input, fields) = opt(|input| {
+    let (input, _) = t_left_paren(input)?;
+    let (input, fields) = separated_list1(t_comma, identifier)(input)?;
+    let (input, _) = t_right_paren(input)?;
+    Ok((input, fields))
+  })(input)?;
+  Ok((input, Node::EnumVariant{name, children: fields.unwrap_or_default()}))
+}
+
+// match_expression = "match" , expression , "{" , match_arm , {"," , match_arm} , [","] , "}" ;
+pub fn match_expression(input: Tokens) -> IResult<Tokens, Node> {
+  let start = input.clone();
+  let (input, _) = t_match(input)?;
+  let (input, scrutinee) = expression(input)?;
+  let (input, _) = t_left_curly(input)?;
+  let (input, arms) = separated_list1(t_comma, match_arm)(input)?;
+  let (input, _) = opt(t_comma)(input)?;
+  let (input, _) = t_right_curly(input)?;
+  let span = span_between(&start, &input);
+  let mut children = vec![scrutinee];
+  children.extend(arms);
+  Ok((input, Node::Match{children, span}))
+}
+
+// try_expression = "try" , block , "catch" , "(" , identifier , ")" , block , ["finally" , block] ;
+// The children are the body, the name the error is bound to, the handler and the optional finally block.
+pub fn try_expression(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_try(input)?;
+  let (input, body) = block(input)?;
+  let (input, _) = t_catch(input)?;
+  let (input, _) = t_left_paren(input)?;
+  let (input, name) = identifier(input)?;
+  let (input, _) = t_right_paren(input)?;
+  let (input, handler) = block(input)?;
+  let (input, finally) = opt(preceded(t_finally, block))(input)?;
+  let mut children = vec![body, name, handler];
+  children.extend(finally);
+  Ok((input, Node::Try{children}))
+}
+
+// match_arm = pattern , ["if" , expression] , "=>" , expression ;
+// The pattern is the first child and the arm body the last one, a MatchGuard sits in between if present.
+pub fn match_arm(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, pattern) = pattern(input)?;
+  let (input, guard) = opt(|input| {
+    let (input, _) = t_if(input)?;
+    let (input, condition) = expression(input)?;
+    Ok((input, Node::MatchGuard{children: vec![condition]}))
+  })(input)?;
+  let (input, _) = t_fat_arrow(input)?;
+  let (input, body) = expression(input)?;
+  let mut children = vec![pattern];
+  children.extend(guard);
+  children.push(body);
+  Ok((input, Node::MatchArm{children}))
+}
+
+// pattern = "_" | number | string | boolean | variant_pattern | identifier ;
+// A bare identifier is a variant without fields if such a variant is defined, otherwise it binds the value to the name.
+pub fn pattern(input: Tokens) -> IResult<Tokens, Node> {
+  alt((wildcard, number, string, boolean, variant_pattern, identifier))(input)
+}
+
+pub fn wildcard(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_underscore(input)?;
+  if matches!(input.tokens.first(), Some(tk) if matches!(tk.kind, TokenKind::Alpha | TokenKind::Digit | TokenKind::Underscore)) {
+    return combinator::fail(input);
+  }
+  Ok((input, Node::Wildcard))
+}
+
+// variant_pattern = identifier , "(" , pattern , {"," , pattern} , ")" ;
+pub fn variant_pattern(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, variant_name) = identifier(input)?;
+  let name = match variant_name {
+    Node::Identifier{value, ..} => value,
+    _ => unreachable!(),
+  };
+  let (input, _) = t_left_paren(input)?;
+  let (input, fields) = separated_list0(t_comma, pattern)(input)?;
+  let (input, _) = t_right_paren(input)?;
+  Ok((input, Node::VariantPattern{name, children: fields}))
+}
+
+// struct_literal = identifier , "{" , field_init , {"," , field_init} , "}" ;
+// At least one field is required, so a block after an identifier is not mistaken for a struct.
+pub fn struct_literal(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, struct_name) = identifier(input)?;
+  let name = match struct_name {
+    Node::Identifier{value, ..} => value,
+    _ => unreachable!(),
+  };
+  let (input, _) = t_left_curly(input)?;
+  let (input, fields) = separated_list1(t_comma, field_init)(input)?;
+  let (input, _) = t_right_curly(input)?;
+  Ok((input, Node::StructLiteral{name, children: fields}))
+}
+
+// field_init = identifier , ":" , expression ;
+pub fn field_init(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, field) = identifier(input)?;
+  let (input, _) = t_colon(input)?;
+  let (input, value) = expression(input)?;
+  Ok((input, Node::KeyValue{children: vec![field, value]}))
+}
+
+// math_expression = term , { ("+" | "-") , term } ;
+// Operators of the same precedence associate to the left. The span of a math expression is the span of its operator.
 pub fn math_expression(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, leftside) = term(input)?;
+  fold_many0(pair(alt((t_plus, t_dash)), term), move || leftside.clone(), math_node)(input)
+}
+
+// term = value , { ("*" | "/") , value } ;
+pub fn term(input: Tokens) -> IResult<Tokens, Node> {
   let (input, leftside) = value(input)?;
-  let (input, operator) = alt((t_plus, t_dash))(input)?;
-  let (input, rightside) = value(input)?;
+  fold_many0(pair(alt((t_asterisk, t_slash)), value), move || leftside.clone(), math_node)(input)
+}
+
+fn math_node(leftside: Node, (operator, rightside): (Token, Node)) -> Node {
   let name = match operator.kind {
     TokenKind::Plus => b"add",
     TokenKind::Dash => b"sub",
+    TokenKind::Asterisk => b"mul",
+    TokenKind::Slash => b"div",
     _ => unreachable!(),
   };
-  Ok((input, Node::MathExpression{name: name.to_vec(), children: vec![leftside, rightside] }))
+  Node::MathExpression{name: name.to_vec(), children: vec![leftside, rightside], span: operator.span() }
 }
 
 pub fn conditional_operator(input: Tokens) -> IResult<Tokens, Token> {
@@ -352,6 +835,7 @@ pub fn conditional_operator(input: Tokens) -> IResult<Tokens, Token> {
 }
 
 // Conditional_expression function
+// The span of a conditional expression is the span of its operator
 pub fn conditional_expression(input: Tokens) -> IResult<Tokens, Node> {
   let (input, left_expr) = lower_precedence_expression(input)?;  
 
@@ -366,7 +850,8 @@ pub fn conditional_expression(input: Tokens) -> IResult<Tokens, Node> {
           // Wrap each child in an expression
           Node::Expression{children: vec! [acc]},
           Node::Expression{children: vec! [right_expr]},
-        ]  
+        ],
+        span: op.span(),
       }
     }
   )(input)
@@ -385,39 +870,175 @@ fn get_operator_name(kind: TokenKind) -> &'static [u8] {
   }
 }
 
-// lower_precedence_expression =  boolean | math_expression | function_call | number | string | identifier ;
+// lower_precedence_expression = math_expression ;
+// A math expression without an operator is just its postfix expression.
 pub fn lower_precedence_expression(input: Tokens) -> IResult<Tokens, Node> {
-  alt((boolean, math_expression, function_call, number, string,identifier))(input)
+  math_expression(input)
 }
 
-// expression = boolean | math_expression | conditional_expression | function_call | number | string | identifier ;
+// expression = conditional_expression ;
+// A conditional expression without an operator is just its lower precedence expression, so this covers
+// boolean | math_expression | function_call | number | string | identifier | block | list as well.
 pub fn expression(input: Tokens) -> IResult<Tokens, Node> {
-  let (input, result) =  alt((boolean, math_expression, conditional_expression, function_call, number,string,identifier))(input)?;
+  let (input, result) = conditional_expression(input)?;
   Ok((input, Node::Expression{children: vec! [result]}))
 }
 
+// block = "{" , {statement} , [expression] , "}" ;
+// The value of a block is its trailing expression. Blocks get their own scope in the interpreter.
+pub fn block(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, children) = block_body(input)?;
+  Ok((input, Node::Block{children}))
+}
+
+// Parses the curly brackets and the statements between them. Shared by blocks and function bodies,
+// the trailing expression (if any) is appended as the last child. Functions can be defined
+// between the statements, they are local to the block.
+fn block_body(input: Tokens) -> IResult<Tokens, Vec<Node>> {
+  let (input, _) = t_left_curly(input)?;
+  let (input, mut statements) = many0(alt((function_define, statement)))(input)?;
+  let (input, tail) = opt(expression)(input)?;
+  let (input, _) = t_right_curly(input)?;
+  statements.extend(tail);
+  Ok((input, statements))
+}
+
 pub fn statement(input: Tokens) -> IResult<Tokens, Node> {
-  let (input, result) = alt((variable_define, expression, function_return))(input)?;
+  let (input, result) = alt((variable_define, assignment, expression, function_return, throw))(input)?;
   let (input, _) = (t_semicolon)(input)?;
   Ok((input, result))
 }
 
 pub fn function_return(input: Tokens) -> IResult<Tokens, Node> {
   let (input, _) = t_return(input)?;
-  let (input, result) = alt((function_call,expression, identifier))(input)?;
+  let (input, result) = expression(input)?;
   Ok((input, Node::FunctionReturn{children: vec! [result]}))
 }
 
+// throw = "throw" , expression ;
+pub fn throw(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_throw(input)?;
+  let (input, result) = expression(input)?;
+  Ok((input, Node::Throw{children: vec! [result]}))
+}
+
+// assignment = postfix_expression , "=" , expression ;
+// The target has to be an element of a collection or a field of a struct, e.g. xs[0] = 1 or p.x = 1
+pub fn assignment(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, target) = postfix_expression(input)?;
+  if !matches!(target, Node::Index{..} | Node::FieldAccess{..}) {
+    return combinator::fail(input);
+  }
+  let (input, _) = t_equal(input)?;
+  let (input, value) = expression(input)?;
+  Ok((input, Node::Assignment{children: vec![target, value]}))
+}
+
+// const_define = "const" , identifier , [":" , type] , "=" , expression , ";" ;
+// Constants are top level items, the children are laid out like the children of a variable definition.
+pub fn const_define(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_const(input)?;
+  let (input, constant) = identifier(input)?;
+  let (input, annotation) = opt(type_annotation)(input)?;
+  let (input, _) = t_equal(input)?;
+  let (input, expression) = expression(input)?;
+  let (input, _) = t_semicolon(input)?;
+  let mut children = vec![constant, expression];
+  children.extend(annotation);
+  Ok((input, Node::ConstDefine{children}))
+}
+
+// variable_define = "let" , identifier , [":" , type] , "=" , expression ;
+// The type annotation (if any) is appended as the last child.
 pub fn variable_define(input: Tokens) -> IResult<Tokens, Node> {
   let (input, _) = t_let(input)?;
   let (input, variable) = identifier(input)?;
+  let (input, annotation) = opt(type_annotation)(input)?;
   let (input, _) = (t_equal)(input)?;
   let (input, expression) = expression(input)?;
-  Ok((input, Node::VariableDefine{children: vec![variable,expression]}))
+  let mut children = vec![variable,expression];
+  children.extend(annotation);
+  Ok((input, Node::VariableDefine{children}))
+}
+
+// ":" , type
+fn type_annotation(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_colon(input)?;
+  type_name(input)
+}
+
+// type = identifier , ["<" , type , {"," , type} , ">"] ;
+// The children are the type arguments, e.g. int in list<int>.
+pub fn type_name(input: Tokens) -> IResult<Tokens, Node> {
+  let start = input.clone();
+  let (input, name) = identifier(input)?;
+  let name = match name {
+    Node::Identifier{value, ..} => value,
+    _ => unreachable!(),
+  };
+  let (input, arguments) = opt(type_arguments)(input)?;
+  let span = span_between(&start, &input);
+  Ok((input, Node::TypeAnnotation{name, children: arguments.unwrap_or_default(), span}))
+}
+
+fn type_arguments(input: Tokens) -> IResult<Tokens, Vec<Node>> {
+  let (input, _) = t_less_than(input)?;
+  let (input, arguments) = separated_list1(t_comma, type_name)(input)?;
+  let (input, _) = t_greater_than(input)?;
+  Ok((input, arguments))
+}
+
+// type_parameters = "<" , identifier , {"," , identifier} , ">" ;
+pub fn type_parameters(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_less_than(input)?;
+  let (input, children) = separated_list1(t_comma, identifier)(input)?;
+  let (input, _) = t_greater_than(input)?;
+  Ok((input, Node::TypeParameters{children}))
+}
+
+// parameters = parameter , {"," , parameter} ;
+// Parameters are Expression nodes like arguments, holding the identifier and the type annotation (if any).
+// The rest parameter comes last and collects the remaining positional arguments into a list.
+pub fn parameters(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, mut children) = separated_list0(t_comma, parameter)(input)?;
+  let (input, rest) = if children.is_empty() {
+    opt(rest_parameter)(input)?
+  } else {
+    opt(preceded(t_comma, rest_parameter))(input)?
+  };
+  children.extend(rest);
+  Ok((input, Node::FunctionArguments{children}))
+}
+
+// rest_parameter = "..." , identifier , [":" , type] ;
+pub fn rest_parameter(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_ellipsis(input)?;
+  let (input, name) = identifier(input)?;
+  let (input, annotation) = opt(type_annotation)(input)?;
+  let mut children = vec![name];
+  children.extend(annotation);
+  Ok((input, Node::Spread{children}))
+}
+
+// parameter = identifier , [":" , type] , ["=" , expression] ;
+// The default value is an Expression child after the identifier and the type annotation.
+pub fn parameter(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, name) = identifier(input)?;
+  let (input, annotation) = opt(type_annotation)(input)?;
+  let (input, default) = opt(default_value)(input)?;
+  let mut children = vec![name];
+  children.extend(annotation);
+  children.extend(default);
+  Ok((input, Node::Expression{children}))
+}
+
+fn default_value(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_equal(input)?;
+  expression(input)
 }
 
 pub fn arguments(input: Tokens) -> IResult<Tokens, Node> {
-  let (input, arg) = expression(input)?;
+  let (input, arg) = argument(input)?;
   let (input, mut others) = many0(other_arg) (input)?;
   let mut args = vec! [arg];
   args.append (&mut others) ;
@@ -427,29 +1048,53 @@ pub fn arguments(input: Tokens) -> IResult<Tokens, Node> {
 
 pub fn other_arg(input: Tokens) -> IResult<Tokens, Node> {
   let (input, _) = t_comma(input)?;
-  expression(input)
+  argument(input)
+}
+
+// argument = [identifier , ":"] , expression | "..." , expression ;
+pub fn argument(input: Tokens) -> IResult<Tokens, Node> {
+  alt((named_argument, spread_argument, expression))(input)
+}
+
+// Passes the elements of a list as positional arguments
+fn spread_argument(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_ellipsis(input)?;
+  let (input, value) = expression(input)?;
+  Ok((input, Node::Spread{children: vec![value]}))
+}
+
+fn named_argument(input: Tokens) -> IResult<Tokens, Node> {
+  let start = input.clone();
+  let (input, argument_name) = identifier(input)?;
+  let (input, _) = t_colon(input)?;
+  let (input, value) = expression(input)?;
+  let name = match argument_name {
+    Node::Identifier{value, ..} => value,
+    _ => unreachable!(),
+  };
+  let span = span_between(&start, &input);
+  Ok((input, Node::NamedArgument{name, children: vec![value], span}))
 }
 
 pub fn function_define(input: Tokens) -> IResult<Tokens, Node> {
   let (input, _) = t_fn(input)?;
   let (input, fxn_name) = identifier(input)?;
   let name = match fxn_name {
-    Node::Identifier{value} => value,
+    Node::Identifier{value, ..} => value,
     _ => unreachable!(),
   };
+  let (input, generics) = opt(type_parameters)(input)?;
   let (input, _) = t_left_paren(input)?;
-  let (input, args) = many0(arguments)(input)?;
+  let (input, fxn_arguments) = parameters(input)?;
   let (input, _) = t_right_paren(input)?;
-  let (input, _) = t_left_curly(input)?;
-  let (input, statements) = many1(statement)(input)?;
-  let (input, _) = t_right_curly(input)?;
+  let (input, return_type) = opt(pair(t_arrow, type_name))(input)?;
+  let (input, statements) = block_body(input)?;
   let fxn_statements = Node::FunctionStatements{children: statements};
-  let fxn_arguments = if args.is_empty() {
-    Node::FunctionArguments{children: vec![]}
-  } else {
-    args[0].clone()
-  };
-  Ok((input, Node::FunctionDefine{name, children: vec![fxn_arguments,fxn_statements] }))
+  // The return type annotation and the type parameters (if any) are appended after the statements
+  let mut children = vec![fxn_arguments,fxn_statements];
+  children.extend(return_type.map(|(_, return_type)| return_type));
+  children.extend(generics);
+  Ok((input, Node::FunctionDefine{name, children }))
 }
 
 pub fn comment(input: Tokens) -> IResult<Tokens, Node> {
@@ -463,7 +1108,29 @@ pub fn comment(input: Tokens) -> IResult<Tokens, Node> {
   Ok((input, Node::Comment{ value: comment_text }))
 }
 
+// import "path"; or import "path" as name;
+// The node holds the path as written, the children are the optional namespace identifier.
+pub fn import(input: Tokens) -> IResult<Tokens, Node> {
+  let (input, _) = t_import(input)?;
+  let (input, path) = import_path(input)?;
+  let (input, alias) = opt(pair(t_as, identifier))(input)?;
+  let (input, _) = t_semicolon(input)?;
+  let children = match alias {
+    Some((_, namespace)) => vec![namespace],
+    None => vec![],
+  };
+  Ok((input, Node::Import{name: path, children}))
+}
+
+// The path of an import is a quoted string that may contain any characters, such as dots and slashes
+fn import_path(input: Tokens) -> IResult<Tokens, Vec<u8>> {
+  let (input, _) = t_quote(input)?;
+  let (input, path) = many1(check_token(&|tk| !matches!(tk.kind, TokenKind::Quote | TokenKind::EOF)))(input)?;
+  let (input, _) = t_quote(input)?;
+  Ok((input, path.into_iter().flat_map(|token| token.lexeme).collect()))
+}
+
 pub fn program(input: Tokens) -> IResult<Tokens, Node> {
-  let (input, result) = many1(alt((function_define,expression,statement,string,boolean,number)))(input)?;
+  let (input, result) = many1(alt((import,const_define,function_define,struct_define,enum_define,statement,expression,string,boolean,number)))(input)?;
   Ok((input, Node::Program{ children: result }))
 }
\ No newline at end of file
@@ -0,0 +1,327 @@
+use crate::parser::Node;
+use crate::lexer::Span;
+use crate::error::*;
+use std::collections::HashMap;
+
+// Where a variable is stored. Frames, closure environments and module globals are lists of values,
+// so reading a variable indexes a list instead of looking up its name.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Binding {
+  // Slot in the frame of the running function, or in the bottom frame for the top level of the program
+  Local(usize),
+  // Position in the environment of the running closure
+  Captured(usize),
+  // Slot in the globals of the module
+  Global(usize),
+}
+
+// The variables of a function that is being resolved
+#[derive(Default)]
+struct FunctionScope {
+  // Slots of the variables in the open scopes, innermost last
+  scopes: Vec<HashMap<String, usize>>,
+  // Number of slots in use. A scope that is closed gives its slots back to the scopes that come after it.
+  slots: usize,
+  // The variables of surrounding functions the closure uses, with their binding in the surrounding function
+  // and the span of the first use
+  captures: Vec<(String, Binding, Span)>,
+  // Lambdas and functions defined in a block capture the variables around them, top level functions only see globals
+  closure: bool,
+}
+
+struct Resolver<'a> {
+  // The functions being resolved, innermost last. The first one is the top level of the program.
+  functions: Vec<FunctionScope>,
+  globals: &'a mut Vec<String>,
+  // Whether a name is a function or an enum variant without fields, the names besides variables that are values
+  is_function: &'a dyn Fn(&str) -> bool,
+  is_unit_variant: &'a dyn Fn(&str) -> bool,
+  // Whether a name can be called without being a variable: a function, an enum variant or a builtin
+  is_callable: &'a dyn Fn(&str) -> bool,
+  // Number of try bodies around the current node
+  tries: usize,
+}
+
+// Binds every variable of a program to the slot it is stored in, replacing the identifiers by Variable nodes.
+// A variable is visible after its definition until the end of the block it is defined in.
+// Variables, constants and import namespaces at the top level are globals and visible everywhere, also in functions
+// defined before them. Their names are added to the globals of the module, the position of a name is its slot.
+// A function frame holds the function itself (if it has a name), then the parameters, then the variables of its body.
+// Lambdas and functions defined in a block refer to the variables of the surrounding functions through their
+// environment. The variables they capture are listed in a Captures node appended to the lambda, and a function
+// defined in a block becomes a variable holding a named lambda. The functions of a block are bound before the
+// statements of the block, so a function can call a function of its block that is defined after it.
+// Calls of a variable become Call nodes, other calls are left to the function table.
+// A name that is not a variable, a function or an enum variant is an error, so is a call of a name that is
+// neither a variable nor callable. Both are reported with the span of the name. An undefined variable in the body
+// of a try is left to the interpreter, which fails when it is read, so the error can be caught.
+pub fn resolve(program: &Node, globals: &mut Vec<String>, is_function: &dyn Fn(&str) -> bool,
+               is_unit_variant: &dyn Fn(&str) -> bool, is_callable: &dyn Fn(&str) -> bool) -> Result<Node, AsaErrorKind> {
+  let mut program = program.clone();
+  let mut resolver = Resolver {
+    functions: vec![FunctionScope { scopes: vec![HashMap::new()], ..FunctionScope::default() }],
+    globals,
+    is_function,
+    is_unit_variant,
+    is_callable,
+    tries: 0,
+  };
+  for item in program.children() {
+    if let Node::VariableDefine { children } | Node::ConstDefine { children } = item {
+      resolver.global(&identifier_name(&children[0])?);
+    }
+  }
+  for item in program.children_mut() {
+    match item {
+      Node::FunctionDefine { name, children } => {
+        let name = name.clone();
+        resolver.resolve_function(Some(&name), children, false)?;
+      }
+      Node::Import { .. } | Node::StructDefine { .. } | Node::EnumDefine { .. } => {},
+      _ => resolver.resolve(item)?,
+    }
+  }
+  Ok(program)
+}
+
+fn identifier_name(node: &Node) -> Result<String, AsaErrorKind> {
+  match node {
+    Node::Identifier { value, .. } => Ok(String::from_utf8_lossy(value).to_string()),
+    _ => Err(AsaErrorKind::Generic("Expected identifier".to_string())),
+  }
+}
+
+// The variable node for a defined identifier
+fn variable(identifier: &Node, binding: Binding) -> Node {
+  match identifier {
+    Node::Identifier { value, span } => Node::Variable { name: value.clone(), binding, span: *span },
+    _ => identifier.clone(),
+  }
+}
+
+impl Resolver<'_> {
+  // The slot of a global, a new one if the module has no global with the name yet
+  fn global(&mut self, name: &str) -> usize {
+    match self.globals.iter().position(|global| global == name) {
+      Some(slot) => slot,
+      None => {
+        self.globals.push(name.to_string());
+        self.globals.len() - 1
+      }
+    }
+  }
+
+  // Defines a variable in the innermost scope. Outside of blocks, the top level defines globals.
+  fn define(&mut self, name: &str) -> Binding {
+    if self.functions.len() == 1 && self.functions[0].scopes.len() == 1 {
+      return Binding::Global(self.global(name));
+    }
+    let function = self.functions.last_mut().unwrap();
+    let slot = function.slots;
+    function.slots += 1;
+    function.scopes.last_mut().unwrap().insert(name.to_string(), slot);
+    Binding::Local(slot)
+  }
+
+  fn lookup(&mut self, name: &str, span: Span) -> Option<Binding> {
+    self.lookup_in(self.functions.len() - 1, name, span)
+  }
+
+  // Finds a variable from the given function outwards. A closure that uses a variable of a surrounding
+  // function captures it, so does every closure in between.
+  fn lookup_in(&mut self, function: usize, name: &str, span: Span) -> Option<Binding> {
+    let scope = &self.functions[function];
+    if let Some(slot) = scope.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+      return Some(Binding::Local(*slot));
+    }
+    if let Some(position) = scope.captures.iter().position(|(captured, ..)| captured == name) {
+      return Some(Binding::Captured(position));
+    }
+    if function > 0 && scope.closure {
+      let binding = self.lookup_in(function - 1, name, span)?;
+      if let Binding::Global(_) = binding {
+        return Some(binding);
+      }
+      let captures = &mut self.functions[function].captures;
+      captures.push((name.to_string(), binding, span));
+      return Some(Binding::Captured(captures.len() - 1));
+    }
+    self.globals.iter().position(|global| global == name).map(Binding::Global)
+  }
+
+  // Runs the resolution of the children of a block in a new scope
+  fn scoped(&mut self, f: impl FnOnce(&mut Self) -> Result<(), AsaErrorKind>) -> Result<(), AsaErrorKind> {
+    let function = self.functions.last_mut().unwrap();
+    let slots = function.slots;
+    function.scopes.push(HashMap::new());
+    let result = f(self);
+    let function = self.functions.last_mut().unwrap();
+    function.scopes.pop();
+    function.slots = slots;
+    result
+  }
+
+  // Resolves the parameters and the body of a function in a new frame, returns the variables it captures.
+  // Default values see the parameters before them.
+  fn resolve_function(&mut self, name: Option<&[u8]>, children: &mut [Node], closure: bool) -> Result<Vec<(String, Binding, Span)>, AsaErrorKind> {
+    self.functions.push(FunctionScope { scopes: vec![HashMap::new()], closure, ..FunctionScope::default() });
+    let result = self.resolve_function_body(name, children);
+    let function = self.functions.pop().unwrap();
+    result.map(|_| function.captures)
+  }
+
+  fn resolve_function_body(&mut self, own_name: Option<&[u8]>, children: &mut [Node]) -> Result<(), AsaErrorKind> {
+    if let Some(name) = own_name {
+      self.define(&String::from_utf8_lossy(name));
+    }
+    for parameter in children[0].children_mut() {
+      let parameter = parameter.children_mut();
+      for default in parameter[1..].iter_mut().filter(|child| matches!(child, Node::Expression { .. })) {
+        self.resolve(default)?;
+      }
+      self.define(&identifier_name(&parameter[0])?);
+    }
+    self.resolve(&mut children[1])
+  }
+
+  fn resolve_all(&mut self, children: &mut [Node]) -> Result<(), AsaErrorKind> {
+    for child in children {
+      self.resolve(child)?;
+    }
+    Ok(())
+  }
+
+  fn resolve(&mut self, node: &mut Node) -> Result<(), AsaErrorKind> {
+    match node {
+      Node::Identifier { value, span } => {
+        let name = String::from_utf8_lossy(value).to_string();
+        match self.lookup(&name, *span) {
+          Some(binding) => *node = Node::Variable { name: name.into_bytes(), binding, span: *span },
+          None if (self.is_function)(&name) || (self.is_unit_variant)(&name) || self.tries > 0 => {},
+          None => return Err(AsaErrorKind::VariableNotDefined { name, span: *span }),
+        }
+      }
+      Node::FunctionCall { name, children, span } => {
+        let callee = String::from_utf8_lossy(name).to_string();
+        match self.lookup(&callee, *span) {
+          Some(binding) => {
+            self.resolve_all(children)?;
+            let mut call = vec![Node::Variable { name: name.clone(), binding, span: *span }];
+            call.append(children);
+            *node = Node::Call { children: call };
+          }
+          None if (self.is_callable)(&callee) => self.resolve_all(children)?,
+          None => return Err(AsaErrorKind::UndefinedFunctionCall { name: callee, span: *span }),
+        }
+      }
+      // The variable is defined after its value, so the value sees an outer variable with the same name
+      Node::VariableDefine { children } | Node::ConstDefine { children } => {
+        self.resolve(&mut children[1])?;
+        let binding = self.define(&identifier_name(&children[0])?);
+        children[0] = variable(&children[0], binding);
+      }
+      Node::Lambda { children } => {
+        let captures = self.resolve_function(None, children, true)?;
+        children.push(captures_node(captures));
+      }
+      Node::FunctionDefine { name, .. } => {
+        let binding = self.define(&String::from_utf8_lossy(name));
+        self.resolve_nested_function(node, binding)?;
+      }
+      // The functions defined in a block get their slots before anything in the block is resolved,
+      // so they are visible in the whole block and can call each other
+      Node::Block { children } | Node::FunctionStatements { children } => {
+        self.scoped(|resolver| {
+          let hoisted: Vec<Option<Binding>> = children.iter().map(|child| match child {
+            Node::FunctionDefine { name, .. } => Some(resolver.define(&String::from_utf8_lossy(name))),
+            _ => None,
+          }).collect();
+          for (child, binding) in children.iter_mut().zip(hoisted) {
+            match binding {
+              Some(binding) => resolver.resolve_nested_function(child, binding)?,
+              None => resolver.resolve(child)?,
+            }
+          }
+          Ok(())
+        })?;
+      }
+      // The names bound by the pattern of an arm are visible in its guard and body
+      Node::Match { children, .. } => {
+        self.resolve(&mut children[0])?;
+        for arm in &mut children[1..] {
+          self.scoped(|resolver| {
+            let children = arm.children_mut();
+            resolver.resolve_pattern(&mut children[0]);
+            resolver.resolve_all(&mut children[1..])
+          })?;
+        }
+      }
+      // The error is bound in a scope around the handler
+      Node::Try { children } => {
+        self.tries += 1;
+        let body = self.resolve(&mut children[0]);
+        self.tries -= 1;
+        body?;
+        let (error, handler) = children[1..].split_at_mut(1);
+        self.scoped(|resolver| {
+          let binding = resolver.define(&identifier_name(&error[0])?);
+          error[0] = variable(&error[0], binding);
+          resolver.resolve(&mut handler[0])
+        })?;
+        self.resolve_all(&mut children[3..])?;
+      }
+      // The first child of a field initializer is the field name
+      Node::StructLiteral { children, .. } => {
+        for field in children {
+          self.resolve(&mut field.children_mut()[1])?;
+        }
+      }
+      Node::Import { .. } | Node::StructDefine { .. } | Node::EnumDefine { .. } | Node::TypeAnnotation { .. } => {},
+      _ => self.resolve_all(node.children_mut())?,
+    }
+    Ok(())
+  }
+
+  // Turns a function defined in a block into a variable holding a named lambda. The function sees the variables
+  // defined before it and the functions of its block, its own name is bound in the function itself.
+  // Function definitions have no span, the nodes made for the name get an empty one.
+  fn resolve_nested_function(&mut self, node: &mut Node, binding: Binding) -> Result<(), AsaErrorKind> {
+    let (name, mut children) = match node {
+      Node::FunctionDefine { name, children } => (std::mem::take(name), std::mem::take(children)),
+      _ => return self.resolve(node),
+    };
+    let captures = self.resolve_function(Some(&name), &mut children, true)?;
+    children.truncate(2);
+    children.push(captures_node(captures));
+    children.push(Node::Identifier { value: name.clone(), span: Span::default() });
+    *node = Node::VariableDefine { children: vec![
+      Node::Variable { name, binding, span: Span::default() },
+      Node::Lambda { children },
+    ]};
+    Ok(())
+  }
+
+  // Defines the names a pattern binds. A name is matched against the enum variant without fields of that name if
+  // there is one, otherwise it binds the value.
+  fn resolve_pattern(&mut self, pattern: &mut Node) {
+    match pattern {
+      Node::Identifier { value, span } if !(self.is_unit_variant)(&String::from_utf8_lossy(value)) => {
+        let name = std::mem::take(value);
+        let binding = self.define(&String::from_utf8_lossy(&name));
+        *pattern = Node::Variable { name, binding, span: *span };
+      }
+      _ => {
+        for child in pattern.children_mut() {
+          self.resolve_pattern(child);
+        }
+      }
+    }
+  }
+}
+
+// The variables a closure captures, read in the frame it is created in
+fn captures_node(captures: Vec<(String, Binding, Span)>) -> Node {
+  Node::Captures { children: captures.into_iter()
+    .map(|(name, binding, span)| Node::Variable { name: name.into_bytes(), binding, span })
+    .collect() }
+}
@@ -0,0 +1,589 @@
+// Static type checking of the optional type annotations, run before the program is executed.
+
+use crate::parser::Node;
+use crate::lexer::Span;
+use crate::error::*;
+use std::collections::{HashMap, HashSet};
+
+// The types the checker knows about. Unknown is the type of everything that cannot be inferred,
+// such as unannotated parameters, and is compatible with every type, so correct unannotated code is never rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+  Int,
+  Bool,
+  String,
+  // Lists and maps with the type of their elements or values
+  List(Box<Type>),
+  Map(Box<Type>),
+  Function,
+  Struct(String),
+  Enum(String),
+  // A type parameter of a generic function. Inside the function it only matches itself.
+  Param(String),
+  // The value of definitions, assignments and blocks that end with one
+  Nil,
+  // The type of return and throw, which do not produce a value where they are. Every type accepts it.
+  Never,
+  Unknown,
+}
+
+impl Type {
+  pub fn name(&self) -> String {
+    match self {
+      Type::Int => "int".to_string(),
+      Type::Bool => "bool".to_string(),
+      Type::String => "string".to_string(),
+      Type::List(element) if **element == Type::Unknown => "list".to_string(),
+      Type::List(element) => format!("list<{}>", element.name()),
+      Type::Map(value) if **value == Type::Unknown => "map".to_string(),
+      Type::Map(value) => format!("map<{}>", value.name()),
+      Type::Function => "fn".to_string(),
+      Type::Struct(name) | Type::Enum(name) | Type::Param(name) => name.clone(),
+      Type::Nil => "nil".to_string(),
+      Type::Never => "never".to_string(),
+      Type::Unknown => "unknown".to_string(),
+    }
+  }
+
+  fn accepts(&self, other: &Type) -> bool {
+    match (self, other) {
+      (Type::Unknown, _) | (_, Type::Unknown) | (_, Type::Never) => true,
+      (Type::List(a), Type::List(b)) | (Type::Map(a), Type::Map(b)) => a.accepts(b),
+      _ => self == other,
+    }
+  }
+
+  // Replaces the type parameters by the types inferred for them. Parameters without a type become Unknown.
+  fn substitute(&self, bindings: &HashMap<String, Type>) -> Type {
+    match self {
+      Type::Param(name) => bindings.get(name).cloned().unwrap_or(Type::Unknown),
+      Type::List(element) => Type::List(Box::new(element.substitute(bindings))),
+      Type::Map(value) => Type::Map(Box::new(value.substitute(bindings))),
+      _ => self.clone(),
+    }
+  }
+
+  // The common type of the branches of a match or try. Branches of different types give Unknown, since a value
+  // of any type may come out of them. Branches that return or throw give no value.
+  fn common(types: Vec<Type>) -> Type {
+    if !types.is_empty() && types.iter().all(|t| *t == Type::Never) {
+      return Type::Never;
+    }
+    let mut types = types.into_iter().filter(|t| *t != Type::Never);
+    let first = types.next().unwrap_or(Type::Unknown);
+    if types.all(|t| t == first) {
+      first
+    } else {
+      Type::Unknown
+    }
+  }
+}
+
+// Type parameters, parameter types and return type of a function, Unknown where there is no annotation.
+// The span of the return type annotation is where a mismatching return value is reported.
+#[derive(Debug, Clone)]
+struct Signature {
+  type_parameters: Vec<String>,
+  // Names of the parameters, for named arguments
+  names: Vec<String>,
+  parameters: Vec<Type>,
+  // Type of the rest parameter, a list
+  rest: Option<Type>,
+  returns: Type,
+  return_span: Span,
+}
+
+// Checks the program against its type annotations and the types inferred for literals, operators and local
+// variables. All mismatches are reported together as TypeErrors, each with the span of the offending code.
+// The body of a generic function is checked once with its type parameters as opaque types, and the type
+// parameters are inferred from the arguments at every call.
+pub fn check_types(program: &Node) -> Result<(), AsaErrorKind> {
+  let mut defined = HashSet::new();
+  let redefined = program.children().iter()
+    .filter_map(|item| match item {
+      Node::VariableDefine {children} | Node::ConstDefine {children} => identifier(&children[0]),
+      _ => None,
+    })
+    .filter(|name| !defined.insert(name.clone()))
+    .collect();
+  let mut checker = TypeChecker {
+    scopes: vec![HashMap::new()],
+    redefined,
+    functions: HashMap::new(),
+    structs: HashSet::new(),
+    variants: HashMap::new(),
+    type_parameters: vec![],
+    returns: vec![],
+    errors: vec![],
+  };
+  checker.declare(program.children());
+  for item in program.children() {
+    checker.infer(item);
+  }
+  if checker.errors.is_empty() {
+    Ok(())
+  } else {
+    Err(AsaErrorKind::TypeErrors(checker.errors))
+  }
+}
+
+struct TypeChecker {
+  // Types of the variables visible at the current node, innermost scope last
+  scopes: Vec<HashMap<String, Type>>,
+  // Globals defined more than once. A function can run after any of the definitions, so inside functions
+  // the type of such a global is not known.
+  redefined: HashSet<String>,
+  // Signatures of the top level functions
+  functions: HashMap<String, Signature>,
+  structs: HashSet<String>,
+  // Key - Variant name
+  // Value - Enum name, whether the variant has fields
+  variants: HashMap<String, (String, bool)>,
+  // Type parameters of the generic functions around the current node
+  type_parameters: Vec<String>,
+  // Return types of the functions around the current node, innermost last
+  returns: Vec<(Type, Span)>,
+  errors: Vec<AsaErrorKind>,
+}
+
+impl TypeChecker {
+  // Collects the types and function signatures defined at the top level, so they can be used before their definition.
+  fn declare(&mut self, items: &[Node]) {
+    for item in items {
+      match item {
+        Node::StructDefine {name, ..} => { self.structs.insert(String::from_utf8_lossy(name).to_string()); },
+        Node::EnumDefine {name, children} => {
+          for variant in children {
+            if let Node::EnumVariant {name: variant, children: fields} = variant {
+              let enum_name = String::from_utf8_lossy(name).to_string();
+              self.variants.insert(String::from_utf8_lossy(variant).to_string(), (enum_name, !fields.is_empty()));
+            }
+          }
+        },
+        _ => {},
+      }
+    }
+    for item in items {
+      if let Node::FunctionDefine {name, children} = item {
+        let signature = self.signature(children);
+        self.functions.insert(String::from_utf8_lossy(name).to_string(), signature);
+      }
+    }
+  }
+
+  // The children of a function definition are the parameters, the statements, and optionally the return type
+  // annotation and the type parameters.
+  fn signature(&mut self, children: &[Node]) -> Signature {
+    let type_parameters: Vec<String> = children[2..].iter()
+      .find_map(|child| match child {
+        Node::TypeParameters {children} => Some(children.iter().filter_map(identifier).collect()),
+        _ => None,
+      })
+      .unwrap_or_default();
+    let outer = self.type_parameters.len();
+    self.type_parameters.extend(type_parameters.clone());
+    let (fixed, rest) = match children[0].children() {
+      [fixed @ .., Node::Spread {children}] => (fixed, Some(children)),
+      fixed => (fixed, None),
+    };
+    let names = fixed.iter()
+      .map(|parameter| parameter.children().first().and_then(identifier).unwrap_or_default())
+      .collect();
+    let parameters = fixed.iter()
+      .map(|parameter| self.annotation(parameter.children().get(1)))
+      .collect();
+    let rest = rest.map(|children| {
+      let list = Type::List(Box::new(Type::Unknown));
+      match self.annotation(children.get(1)) {
+        Type::Unknown => list,
+        t => {
+          if let Some(Node::TypeAnnotation {span, ..}) = children.get(1) {
+            self.expect(&list, &t, *span);
+          }
+          t
+        }
+      }
+    });
+    let (returns, return_span) = match children[2..].iter().find(|child| matches!(child, Node::TypeAnnotation {..})) {
+      Some(annotation @ Node::TypeAnnotation {span, ..}) => (self.annotation(Some(annotation)), *span),
+      _ => (Type::Unknown, Span::default()),
+    };
+    self.type_parameters.truncate(outer);
+    Signature { type_parameters, names, parameters, rest, returns, return_span }
+  }
+
+  // The type of an optional annotation node
+  fn annotation(&mut self, node: Option<&Node>) -> Type {
+    let (name, arguments, span) = match node {
+      Some(Node::TypeAnnotation {name, children, span}) => (String::from_utf8_lossy(name).to_string(), children, *span),
+      _ => return Type::Unknown,
+    };
+    let mut arguments: Vec<Type> = arguments.iter().map(|argument| self.annotation(Some(argument))).collect();
+    let expected = match name.as_str() {
+      "list" | "map" => 1,
+      _ => 0,
+    };
+    if arguments.len() > expected {
+      self.errors.push(AsaErrorKind::TypeMismatch {
+        expected: format!("{} type arguments for {}", expected, name),
+        found: format!("{} type arguments", arguments.len()),
+        span,
+      });
+      return Type::Unknown;
+    }
+    let argument = Box::new(arguments.pop().unwrap_or(Type::Unknown));
+    match name.as_str() {
+      "int" => Type::Int,
+      "bool" => Type::Bool,
+      "string" => Type::String,
+      "list" => Type::List(argument),
+      "map" => Type::Map(argument),
+      _ if self.type_parameters.contains(&name) => Type::Param(name),
+      _ if self.structs.contains(&name) => Type::Struct(name),
+      _ if self.variants.values().any(|(enum_name, _)| *enum_name == name) => Type::Enum(name),
+      _ => {
+        self.errors.push(AsaErrorKind::UndefinedTypeAnnotation { name, span });
+        Type::Unknown
+      }
+    }
+  }
+
+  fn expect(&mut self, expected: &Type, found: &Type, span: Span) {
+    if !expected.accepts(found) {
+      self.errors.push(AsaErrorKind::TypeMismatch { expected: expected.name(), found: found.name(), span });
+    }
+  }
+
+  // Checks both operands of an operator, reporting the operator once if they do not match
+  fn expect_operands(&mut self, expected: &Type, left: &Type, right: &Type, span: Span) {
+    if expected.accepts(left) {
+      self.expect(expected, right, span);
+    } else {
+      self.expect(expected, left, span);
+    }
+  }
+
+  // Matches the type of an argument against a parameter type of a generic function, inferring the type parameters.
+  // A type parameter that was already inferred from an earlier argument has to match again.
+  fn unify(&mut self, parameter: &Type, argument: &Type, bindings: &mut HashMap<String, Type>, span: Span) {
+    match (parameter, argument) {
+      (Type::Param(name), _) => match bindings.get(name) {
+        Some(bound) if *bound != Type::Unknown => {
+          if !bound.accepts(argument) {
+            self.errors.push(AsaErrorKind::TypeParameterMismatch {
+              parameter: name.clone(),
+              expected: bound.name(),
+              found: argument.name(),
+              span,
+            });
+          }
+        }
+        _ => { bindings.insert(name.clone(), argument.clone()); },
+      },
+      (Type::List(parameter), Type::List(argument)) | (Type::Map(parameter), Type::Map(argument)) => {
+        self.unify(parameter, argument, bindings, span);
+      }
+      _ => {
+        let expected = parameter.substitute(bindings);
+        self.expect(&expected, argument, span);
+      }
+    }
+  }
+
+  // The type of a variable, from the innermost scope outwards. The outermost scope holds the globals.
+  fn lookup(&self, name: &str) -> Option<Type> {
+    let (depth, t) = self.scopes.iter().enumerate().rev().find_map(|(depth, scope)| Some((depth, scope.get(name)?)))?;
+    if depth == 0 && !self.returns.is_empty() && self.redefined.contains(name) {
+      return Some(Type::Unknown);
+    }
+    Some(t.clone())
+  }
+
+  fn bind(&mut self, name: String, t: Type) {
+    if let Some(scope) = self.scopes.last_mut() {
+      scope.insert(name, t);
+    }
+  }
+
+  // Infers the type of a node, recording the mismatches found on the way
+  fn infer(&mut self, node: &Node) -> Type {
+    match node {
+      Node::Number {..} | Node::BigInt {..} | Node::LargeNumber {..} => Type::Int,
+      Node::Bool {..} => Type::Bool,
+      Node::String {..} => Type::String,
+      // nil stands in for a missing value of any type
+      Node::Null => Type::Unknown,
+      Node::Interpolation {children} => {
+        children.iter().for_each(|child| { self.infer(child); });
+        Type::String
+      }
+      Node::NamedArgument {children, ..} => self.infer(&children[0]),
+      Node::Spread {children} => {
+        self.infer(&children[0]);
+        Type::Unknown
+      }
+      // The elements of a list or map can be replaced by values of any type, so the element type is only known
+      // from an annotation. A literal holds elements of any type.
+      Node::List {children} => {
+        children.iter().for_each(|child| { self.infer(child); });
+        Type::List(Box::new(Type::Unknown))
+      }
+      Node::Map {children} => {
+        children.iter().for_each(|child| { self.infer(&child.children()[1]); });
+        Type::Map(Box::new(Type::Unknown))
+      }
+      Node::StructLiteral {name, children} => {
+        children.iter().for_each(|child| { self.infer(child); });
+        Type::Struct(String::from_utf8_lossy(name).to_string())
+      }
+      Node::Expression {children} => self.infer(&children[0]),
+      Node::Identifier {value, .. } => {
+        let name = String::from_utf8_lossy(value).to_string();
+        if let Some(t) = self.lookup(&name) {
+          return t;
+        }
+        match self.variants.get(&name) {
+          Some((enum_name, false)) => Type::Enum(enum_name.clone()),
+          _ if self.functions.contains_key(&name) => Type::Function,
+          _ => Type::Unknown,
+        }
+      }
+      Node::MathExpression {name, children, span} => {
+        let left = self.infer(&children[0]);
+        let right = self.infer(&children[1]);
+        // + concatenates strings, the operand types decide which operation it is
+        let operands = match (name.as_slice(), &left, &right) {
+          (b"add", Type::String, _) | (b"add", _, Type::String) => Type::String,
+          (b"add", Type::Unknown, Type::Unknown) => return Type::Unknown,
+          _ => Type::Int,
+        };
+        self.expect_operands(&operands, &left, &right, *span);
+        operands
+      }
+      Node::ConditionalExpression {name, children, span} => {
+        let left = self.infer(&children[0]);
+        let right = self.infer(&children[1]);
+        // Any two values can be compared for equality, values of different types are never equal.
+        // Ordering compares two numbers or two strings.
+        if !matches!(name.as_slice(), b"eq_" | b"neq") {
+          let operands = match (&left, &right) {
+            (Type::String, _) | (_, Type::String) => Type::String,
+            _ => Type::Int,
+          };
+          self.expect_operands(&operands, &left, &right, *span);
+        }
+        Type::Bool
+      }
+      Node::FunctionCall {name, children, span} => {
+        let arguments: Vec<Type> = children[0].children().iter().map(|argument| self.infer(argument)).collect();
+        let name = String::from_utf8_lossy(name).to_string();
+        // A variable holding a function takes precedence, its signature is not known
+        if self.lookup(&name).is_some() {
+          return Type::Unknown;
+        }
+        if let Some(signature) = self.functions.get(&name).cloned() {
+          let mut bindings = HashMap::new();
+          let rest_element = match &signature.rest {
+            Some(Type::List(element)) => Some(*element.clone()),
+            Some(_) => Some(Type::Unknown),
+            None => None,
+          };
+          // The positions of the arguments after a spread argument are only known when the call runs
+          let positional = children[0].children().iter().take_while(|argument| !matches!(argument, Node::Spread {..}));
+          for (index, (argument, argument_type)) in positional.zip(&arguments).enumerate() {
+            // Unknown argument names are reported when the call runs
+            let parameter = match argument {
+              Node::NamedArgument {name, ..} => signature.names.iter()
+                .position(|parameter| parameter.as_bytes() == name.as_slice())
+                .and_then(|position| signature.parameters.get(position)),
+              _ => signature.parameters.get(index).or(rest_element.as_ref()),
+            };
+            if let Some(parameter) = parameter {
+              self.unify(parameter, argument_type, &mut bindings, *span);
+            }
+          }
+          return signature.returns.substitute(&bindings);
+        }
+        if let Some((enum_name, _)) = self.variants.get(&name) {
+          return Type::Enum(enum_name.clone());
+        }
+        builtin_type(&name, &arguments)
+      }
+      Node::Index {children} => {
+        let target = self.infer(&children[0]);
+        self.infer(&children[1]);
+        match target {
+          Type::List(element) | Type::Map(element) => *element,
+          _ => Type::Unknown,
+        }
+      }
+      Node::Lambda {children} => {
+        let signature = self.signature(children);
+        self.check_function(children, &signature);
+        Type::Function
+      }
+      // The signatures of top level functions are known from declare, nested functions are local variables
+      Node::FunctionDefine {name, children} => {
+        let name = String::from_utf8_lossy(name).to_string();
+        let signature = match self.functions.get(&name) {
+          Some(signature) if self.scopes.len() == 1 => signature.clone(),
+          _ => {
+            self.bind(name, Type::Function);
+            self.signature(children)
+          }
+        };
+        let outer = self.type_parameters.len();
+        self.type_parameters.extend(signature.type_parameters.clone());
+        self.check_function(children, &signature);
+        self.type_parameters.truncate(outer);
+        Type::Nil
+      }
+      Node::Block {children} => {
+        self.scopes.push(HashMap::new());
+        let t = self.infer_statements(children);
+        self.scopes.pop();
+        t
+      }
+      // The error is a thrown value or an Error struct, its type is not known
+      Node::Try {children} => {
+        let body = self.infer(&children[0]);
+        self.scopes.push(HashMap::new());
+        if let Some(name) = identifier(&children[1]) {
+          self.bind(name, Type::Unknown);
+        }
+        let handler = self.infer(&children[2]);
+        self.scopes.pop();
+        if let Some(finally) = children.get(3) {
+          self.infer(finally);
+        }
+        Type::common(vec![body, handler])
+      }
+      Node::Throw {children} => {
+        self.infer(&children[0]);
+        Type::Never
+      }
+      Node::VariableDefine {children} | Node::ConstDefine {children} => {
+        let name = match &children[0] {
+          Node::Identifier {value, .. } => String::from_utf8_lossy(value).to_string(),
+          _ => return Type::Unknown,
+        };
+        let value = self.infer(&children[1]);
+        let t = match children.get(2) {
+          Some(annotation @ Node::TypeAnnotation {span, ..}) => {
+            let declared = self.annotation(Some(annotation));
+            self.expect(&declared, &value, *span);
+            declared
+          }
+          _ => value,
+        };
+        // A definition is a statement, its value is nil
+        self.bind(name, t);
+        Type::Nil
+      }
+      Node::FunctionReturn {children} => {
+        let t = self.infer(&children[0]);
+        if let Some((returns, span)) = self.returns.last().cloned() {
+          self.expect(&returns, &t, span);
+        }
+        Type::Never
+      }
+      // The value of a match is the value of the body of the arm that matches
+      Node::Match {children, ..} => {
+        self.infer(&children[0]);
+        let mut arms = vec![];
+        for arm in &children[1..] {
+          self.scopes.push(HashMap::new());
+          self.bind_pattern(&arm.children()[0]);
+          let t = arm.children()[1..].iter().fold(Type::Unknown, |_, child| self.infer(child));
+          arms.push(t);
+          self.scopes.pop();
+        }
+        Type::common(arms)
+      }
+      Node::Assignment {children} => {
+        self.infer(&children[0]);
+        self.infer(&children[1]);
+        Type::Nil
+      }
+      Node::Import {..} | Node::StructDefine {..} | Node::EnumDefine {..} => Type::Unknown,
+      _ => {
+        node.children().iter().for_each(|child| { self.infer(child); });
+        Type::Unknown
+      }
+    }
+  }
+
+  // The type of a block is the type of its last statement, a block without statements is nil
+  fn infer_statements(&mut self, statements: &[Node]) -> Type {
+    let mut t = Type::Nil;
+    for statement in statements {
+      t = self.infer(statement);
+    }
+    t
+  }
+
+  // Checks the body of a function with its parameters in scope. The value the body ends with is returned as well,
+  // so a function with a return type has to return on every path or end with a value of that type.
+  // A default value has to match the annotation of its parameter and can use the parameters before it.
+  fn check_function(&mut self, children: &[Node], signature: &Signature) {
+    self.scopes.push(HashMap::new());
+    let mut parameters = signature.parameters.iter();
+    for parameter in children[0].children() {
+      if let Node::Spread {children} = parameter {
+        if let (Some(name), Some(t)) = (children.first().and_then(identifier), &signature.rest) {
+          self.bind(name, t.clone());
+        }
+        continue;
+      }
+      let t = parameters.next().cloned().unwrap_or(Type::Unknown);
+      if let Some(Node::Identifier {value, .. }) = parameter.children().first() {
+        if let Some(default @ Node::Expression {..}) = parameter.children().last() {
+          let found = self.infer(default);
+          if let Some(Node::TypeAnnotation {span, ..}) = parameter.children().get(1) {
+            self.expect(&t, &found, *span);
+          }
+        }
+        self.bind(String::from_utf8_lossy(value).to_string(), t);
+      }
+    }
+
+    self.returns.push((signature.returns.clone(), signature.return_span));
+    let t = self.infer_statements(children[1].children());
+    self.expect(&signature.returns, &t, signature.return_span);
+    self.returns.pop();
+    self.scopes.pop();
+  }
+
+  fn bind_pattern(&mut self, pattern: &Node) {
+    match pattern {
+      Node::Identifier {value, .. } => {
+        let name = String::from_utf8_lossy(value).to_string();
+        match self.variants.get(&name) {
+          Some((_, false)) => {},
+          _ => self.bind(name, Type::Unknown),
+        }
+      }
+      _ => pattern.children().iter().for_each(|child| self.bind_pattern(child)),
+    }
+  }
+}
+
+fn identifier(node: &Node) -> Option<String> {
+  match node {
+    Node::Identifier {value, .. } => Some(String::from_utf8_lossy(value).to_string()),
+    _ => None,
+  }
+}
+
+// Result types of the builtins, see builtins.rs. Like literals, the lists they return hold elements of any type.
+fn builtin_type(name: &str, arguments: &[Type]) -> Type {
+  let first = arguments.first().cloned().unwrap_or(Type::Unknown);
+  match (name, first) {
+    ("len", _) => Type::Int,
+    ("has", _) => Type::Bool,
+    ("keys", _) => Type::List(Box::new(Type::String)),
+    ("pop", Type::List(element)) => *element,
+    ("values" | "push" | "slice" | "concat", _) => Type::List(Box::new(Type::Unknown)),
+    _ => Type::Unknown,
+  }
+}
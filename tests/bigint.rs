@@ -0,0 +1,65 @@
+extern crate asalang;
+
+use asalang::BigInt;
+
+fn big(text: &str) -> BigInt {
+  text.parse().unwrap()
+}
+
+fn factorial(n: i32) -> BigInt {
+  (1..=n).fold(BigInt::from(1), |product, factor| &product * &BigInt::from(factor))
+}
+
+fn power(base: i32, exponent: u32) -> BigInt {
+  (0..exponent).fold(BigInt::from(1), |product, _| &product * &BigInt::from(base))
+}
+
+#[test]
+fn bigint_factorials() {
+  assert_eq!(factorial(25).to_string(), "15511210043330985984000000");
+  assert_eq!(factorial(30).to_string(), "265252859812191058636308480000000");
+  assert_eq!(factorial(50).to_string(), "30414093201713378043612608166064768844377641568960512000000000000");
+}
+
+#[test]
+fn bigint_powers() {
+  assert_eq!(power(2, 100).to_string(), "1267650600228229401496703205376");
+  assert_eq!(power(3, 50).to_string(), "717897987691852588770249");
+  assert_eq!(power(-7, 41).to_string(), "-44567640326363195900190045974568007");
+}
+
+#[test]
+fn bigint_division_truncates() {
+  assert_eq!(factorial(50).checked_div(&power(2, 100)), Some(big("23992489094579836602562625279676826")));
+  assert_eq!((-&factorial(30)).checked_div(&power(10, 15)), Some(big("-265252859812191058")));
+  assert_eq!(big("7").checked_div(&big("-2")), Some(big("-3")));
+  assert_eq!(big("1").checked_div(&big("0")), None);
+}
+
+#[test]
+fn bigint_addition_and_subtraction() {
+  let large = power(10, 30);
+  assert_eq!((&large - &BigInt::from(1)).to_string(), "999999999999999999999999999999");
+  assert_eq!((&BigInt::from(1) - &large).to_string(), "-999999999999999999999999999999");
+  assert_eq!(&(&large + &big("-1")) + &BigInt::from(1), large);
+  assert_eq!((&large - &large).to_string(), "0");
+}
+
+#[test]
+fn bigint_parse_and_display() {
+  assert_eq!(big("-000123").to_string(), "-123");
+  assert_eq!(big("-0").to_string(), "0");
+  assert_eq!(big("1000000000").to_string(), "1000000000");
+  assert_eq!(big("1000000000000000001").to_string(), "1000000000000000001");
+  assert!("12a".parse::<BigInt>().is_err());
+  assert!("".parse::<BigInt>().is_err());
+}
+
+#[test]
+fn bigint_ordering() {
+  assert!(big("-5") < big("-4"));
+  assert!(big("-1000000000000") < big("3"));
+  assert!(power(2, 64) > power(2, 63));
+  assert_eq!(BigInt::from(i32::MIN).to_i32(), Some(i32::MIN));
+  assert_eq!(big("2147483648").to_i32(), None);
+}
@@ -3,7 +3,6 @@ extern crate nom;
 use std::io::Write;
 
 use asalang::*;
-use nom::IResult;
 
 macro_rules! test_fragment {
   ($func:ident, $test:tt, $expected:expr) => (
@@ -15,7 +14,7 @@ macro_rules! test_fragment {
           assert_eq!(tokens.is_done(), true); // Check that input token stream is fully parsed
           let mut interpreter = Interpreter::new();
           let result = interpreter.exec(&tree);
-          std::io::stdout().flush();
+          std::io::stdout().flush().unwrap();
           assert_eq!(result, $expected);
           Ok(())
         },
@@ -34,8 +33,8 @@ macro_rules! test_program {
         Ok((tokens, tree)) => {
           assert_eq!(tokens.is_done(), true); // Check that input token stream is fully parsed
           let mut interpreter = Interpreter::new();
-          let compile_result = interpreter.exec(&tree)?;
-          let main_result = interpreter.start_main(vec![]);
+          // Executing the program calls main
+          let main_result = interpreter.exec(&tree);
           assert_eq!(main_result, $expected);
           Ok(())
         },
@@ -50,21 +49,21 @@ test_fragment!(interpreter_numeric, r#"123"#, Ok(Value::Number(123)));
 test_fragment!(interpreter_string, r#""helloworld""#, Ok(Value::String("helloworld".to_string())));
 test_fragment!(interpreter_bool_true, r#"true"#, Ok(Value::Bool(true)));
 test_fragment!(interpreter_bool_false, r#"false"#, Ok(Value::Bool(false)));
-test_fragment!(interpreter_identifier, r#"x"#, Err(AsaErrorKind::UndefinedFunction));
-test_fragment!(interpreter_function_call, r#"foo()"#, Err(AsaErrorKind::UndefinedFunction));
-test_fragment!(interpreter_function_call_one_arg, r#"foo(a)"#, Err(AsaErrorKind::UndefinedFunction));
-test_fragment!(interpreter_function_call_more_args, r#"foo(a,b,c)"#, Err(AsaErrorKind::UndefinedFunction));
-test_fragment!(interpreter_variable_define, r#"let x = 123;"#, Ok(Value::Number(123)));
-test_fragment!(interpreter_variable_init, r#"let x = 1;"#, Ok(Value::Number(1)));
-test_fragment!(interpreter_variable_bool, r#"let bool = true;"#, Ok(Value::Bool(true)));
-test_fragment!(interpreter_variable_string, r#"let string = "HelloWorld";"#, Ok(Value::String("HelloWorld".to_string())));
-test_fragment!(interpreter_variable_init_no_space, r#"let x=1;"#, Ok(Value::Number(1)));
+test_fragment!(interpreter_identifier, r#"x"#, Err(AsaErrorKind::VariableNotDefined { name: "x".to_string(), span: Span::new(1, 1, 1, 1) }));
+test_fragment!(interpreter_function_call, r#"foo()"#, Err(AsaErrorKind::UndefinedFunctionCall { name: "foo".to_string(), span: Span::new(1, 1, 1, 5) }));
+test_fragment!(interpreter_function_call_one_arg, r#"foo(a)"#, Err(AsaErrorKind::UndefinedFunctionCall { name: "foo".to_string(), span: Span::new(1, 1, 1, 6) }));
+test_fragment!(interpreter_function_call_more_args, r#"foo(a,b,c)"#, Err(AsaErrorKind::UndefinedFunctionCall { name: "foo".to_string(), span: Span::new(1, 1, 1, 10) }));
+test_fragment!(interpreter_variable_define, r#"let x = 123;"#, Ok(Value::Nil));
+test_fragment!(interpreter_variable_init, r#"let x = 1;"#, Ok(Value::Nil));
+test_fragment!(interpreter_variable_bool, r#"let bool = true;"#, Ok(Value::Nil));
+test_fragment!(interpreter_variable_string, r#"let string = "HelloWorld";"#, Ok(Value::Nil));
+test_fragment!(interpreter_variable_init_no_space, r#"let x=1;"#, Ok(Value::Nil));
 test_fragment!(interpreter_math, r#"1 + 1"#, Ok(Value::Number(2)));
 test_fragment!(interpreter_math_no_space, r#"1-1"#, Ok(Value::Number(0)));
 test_fragment!(interpreter_math_multiply, r#"2 + 4"#, Ok(Value::Number(6)));
-test_fragment!(interpreter_assign_math, r#"let x = 1 + 1;"#, Ok(Value::Number(2)));
-test_fragment!(interpreter_assign_function, r#"let x = foo();"#, Err(AsaErrorKind::UndefinedFunction));
-test_fragment!(interpreter_assign_function_arguments, r#"let x = foo(a,b,c);"#, Err(AsaErrorKind::UndefinedFunction));
+test_fragment!(interpreter_assign_math, r#"let x = 1 + 1;"#, Ok(Value::Nil));
+test_fragment!(interpreter_assign_function, r#"let x = foo();"#, Err(AsaErrorKind::UndefinedFunctionCall { name: "foo".to_string(), span: Span::new(1, 9, 1, 13) }));
+test_fragment!(interpreter_assign_function_arguments, r#"let x = foo(a,b,c);"#, Err(AsaErrorKind::UndefinedFunctionCall { name: "foo".to_string(), span: Span::new(1, 9, 1, 18) }));
 
 // Test full programs
 test_program!(interpreter_define_function, r#"fn main(){return foo();} fn foo(){return 5;}"#, Ok(Value::Number(5)));
@@ -138,7 +137,7 @@ fn main() {
   let y = 5;
   let result = x < y != false; 
 }
-"#, Ok(Value::Bool(false)));
+"#, Ok(Value::Nil));
 
 // 5. Assign boolean and compare with another variable holding comparison
 // true != false ==> true
@@ -148,13 +147,17 @@ fn main() {
   let y = 5 > 5;
   let result = x != y;
 }
-"#, Ok(Value::Bool(true)));
+"#, Ok(Value::Nil));
 
 // 6. Test an invalid case
-test_fragment!(interpreter_invalid_comparison, r#"1 < false"#, Err(AsaErrorKind::Generic("Mismatched types in conditional expression".to_string())));
+test_fragment!(interpreter_invalid_comparison, r#"1 < false"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "int".to_string(), found: "bool".to_string(), span: Span::new(1, 3, 1, 3) },
+])));
 
 // 7. Test another invalid case
-test_fragment!(interpreter_invalid_comparison_2, r#"10 - false"#, Err(AsaErrorKind::Generic("MathOperationError".to_string())));
+test_fragment!(interpreter_invalid_comparison_2, r#"10 - false"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "int".to_string(), found: "bool".to_string(), span: Span::new(1, 4, 1, 4) },
+])));
 
 // 8. Test full program with multiple functions and comparison
 test_program!(interpreter_nested_function_calculations, r#"
@@ -167,7 +170,7 @@ fn main() {
   let y = calculate(5, 5);
   let result = x > y == true;
 }
-"#, Ok(Value::Bool(true)));
+"#, Ok(Value::Nil));
 
 // 9. Invalid math operation due to type mismatch test
 test_program!(interpreter_mixed_type_error_handling, r#"
@@ -176,7 +179,9 @@ fn main() {
   let y = 10;
   let result = x + y;  
 }
-"#, Err(AsaErrorKind::Generic("MathOperationError".to_string())));
+"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "int".to_string(), found: "bool".to_string(), span: Span::new(5, 18, 5, 18) },
+])));
 
 // 10. Long program test
 test_program!(interpreter_function_result_comparison, r#"
@@ -188,4 +193,1857 @@ fn main() {
   let x = add(20, 10);
   let result = x > 20; 
 }
-"#, Ok(Value::Bool(true)));
\ No newline at end of file
+"#, Ok(Value::Nil));
+
+// The comparisons of the programs above, returned from main
+test_program!(interpreter_nested_assign_and_compare_boolean_returned, r#"
+fn main() {
+  let x = 10;
+  let y = 5;
+  let result = x < y != false;
+  return result;
+}
+"#, Ok(Value::Bool(false)));
+test_program!(interpreter_nested_assign_and_compare_boolean_and_math_returned, r#"
+fn main() {
+  let x = true;
+  let y = 5 > 5;
+  let result = x != y;
+  return result;
+}
+"#, Ok(Value::Bool(true)));
+test_program!(interpreter_nested_function_calculations_returned, r#"
+fn calculate(a, b) {
+  return a + b;
+}
+
+fn main() {
+  let x = calculate(15, 16);
+  let y = calculate(5, 5);
+  let result = x > y == true;
+  return result;
+}
+"#, Ok(Value::Bool(true)));
+test_program!(interpreter_function_result_comparison_returned, r#"
+fn add(a, b) {
+  return a + b;
+}
+
+fn main() {
+  let x = add(20, 10);
+  let result = x > 20;
+  return result;
+}
+"#, Ok(Value::Bool(true)));
+// Block expressions
+test_fragment!(interpreter_block_value, r#"{let x = 2; x + 1}"#, Ok(Value::Number(3)));
+test_program!(interpreter_block_shadowing, r#"
+fn main() {
+  let x = 1;
+  let y = {
+    let x = 10;
+    x + 1
+  };
+  return x + y;
+}
+"#, Ok(Value::Number(12)));
+test_program!(interpreter_block_scope_dropped, r#"
+fn main() {
+  let y = {
+    let x = 10;
+    x
+  };
+  return x;
+}
+"#, Err(AsaErrorKind::VariableNotDefined { name: "x".to_string(), span: Span::new(7, 10, 7, 10) }));
+test_program!(interpreter_function_body_trailing_expression, r#"
+fn main() {
+  let x = 4;
+  x + 1
+}
+"#, Ok(Value::Number(5)));
+
+// Lists
+test_fragment!(interpreter_list_literal, r#"[1, 2 + 3, true]"#, Ok(Value::list(vec![Value::Number(1), Value::Number(5), Value::Bool(true)])));
+test_fragment!(interpreter_list_index, r#"[10, 20, 30][1]"#, Ok(Value::Number(20)));
+test_fragment!(interpreter_list_index_out_of_bounds, r#"[10, 20, 30][3]"#, Err(AsaErrorKind::IndexOutOfBounds { index: 3, length: 3 }));
+test_program!(interpreter_list_index_assignment, r#"
+fn main() {
+  let xs = [1, 2, 3];
+  xs[1] = 20;
+  return xs[0] + xs[1];
+}
+"#, Ok(Value::Number(21)));
+test_program!(interpreter_list_assignment_out_of_bounds, r#"
+fn main() {
+  let xs = [1, 2, 3];
+  xs[5] = 20;
+}
+"#, Err(AsaErrorKind::IndexOutOfBounds { index: 5, length: 3 }));
+test_program!(interpreter_list_builtins, r#"
+fn main() {
+  let xs = [1, 2];
+  push(xs, 3);
+  push(xs, 4);
+  let last = pop(xs);
+  let ys = concat(slice(xs, 1, 3), [last]);
+  return ys;
+}
+"#, Ok(Value::list(vec![Value::Number(2), Value::Number(3), Value::Number(4)])));
+test_program!(interpreter_list_len, r#"
+fn main() {
+  let xs = [1, 2, 3];
+  return len(xs) - 1;
+}
+"#, Ok(Value::Number(2)));
+test_program!(interpreter_list_shared_with_function, r#"
+fn add(xs, x) {
+  push(xs, x);
+}
+
+fn main() {
+  let xs = [];
+  add(xs, 7);
+  return xs[0];
+}
+"#, Ok(Value::Number(7)));
+test_fragment!(interpreter_list_pop_empty, r#"pop([])"#, Err(AsaErrorKind::EmptyList));
+test_fragment!(interpreter_list_slice_reversed, r#"slice([1, 2, 3], 2, 1)"#, Err(AsaErrorKind::InvalidSlice { start: 2, end: 1 }));
+// A list that contains itself can be compared and shown
+test_fragment!(interpreter_list_cyclic_equal, r#"
+let xs = [];
+push(xs, xs);
+xs == xs
+"#, Ok(Value::Bool(true)));
+test_fragment!(interpreter_list_cyclic_compare, r#"
+let a = [1];
+push(a, a);
+let b = [1];
+push(b, b);
+let c = [2];
+push(c, c);
+let same = a == b;
+let different = a == c;
+let m = {};
+m["self"] = m;
+[same, different, m == {"self": m}]
+"#, Ok(Value::list(vec![Value::Bool(true), Value::Bool(false), Value::Bool(true)])));
+test_fragment!(interpreter_list_cyclic_display, r#"
+let xs = [1];
+push(xs, xs);
+let m = {"xs": xs};
+push(xs, m);
+"{xs}"
+"#, Ok(Value::String(r#"[1, ..., {"xs": ...}]"#.to_string())));
+
+// The host sees a list that contains itself through Debug, e.g. when printing a result
+#[test]
+fn interpreter_list_cyclic_debug() {
+  let (_, tree) = program(lex("let xs = [1]; push(xs, xs); let m = {\"xs\": xs}; push(xs, m); xs")).unwrap();
+  let xs = Interpreter::new().exec(&tree).unwrap();
+  assert_eq!(format!("{:?}", xs), r#"List([Number(1), ..., Map([("xs", ...)])])"#);
+}
+
+// Maps
+test_fragment!(interpreter_map_literal, r#"{"b": 1, "a": true}"#, Ok(Value::map(vec![
+  ("b".to_string(), Value::Number(1)),
+  ("a".to_string(), Value::Bool(true)),
+])));
+test_fragment!(interpreter_map_empty, r#"{}"#, Ok(Value::map(vec![])));
+test_fragment!(interpreter_map_access, r#"{"a": 1, "b": 2}["b"]"#, Ok(Value::Number(2)));
+test_fragment!(interpreter_map_missing_key, r#"{"a": 1}["c"]"#, Err(AsaErrorKind::KeyNotFound("c".to_string())));
+test_program!(interpreter_map_assignment, r#"
+fn main() {
+  let config = {"port": 80};
+  config["port"] = 8080;
+  config["host"] = "localhost";
+  return config;
+}
+"#, Ok(Value::map(vec![
+  ("port".to_string(), Value::Number(8080)),
+  ("host".to_string(), Value::String("localhost".to_string())),
+])));
+test_program!(interpreter_map_keys_values, r#"
+fn main() {
+  let m = {"z": 1, "a": 2};
+  m["m"] = 3;
+  return [keys(m), values(m)];
+}
+"#, Ok(Value::list(vec![
+  Value::list(vec![Value::String("z".to_string()), Value::String("a".to_string()), Value::String("m".to_string())]),
+  Value::list(vec![Value::Number(1), Value::Number(2), Value::Number(3)]),
+])));
+test_program!(interpreter_map_has, r#"
+fn main() {
+  let m = {"a": 1};
+  return [has(m, "a"), has(m, "b")];
+}
+"#, Ok(Value::list(vec![Value::Bool(true), Value::Bool(false)])));
+
+// Structs
+test_program!(interpreter_struct_field_read, r#"
+struct Point { x, y }
+
+fn main() {
+  let p = Point { x: 1, y: 2 };
+  return p.x + p.y;
+}
+"#, Ok(Value::Number(3)));
+test_program!(interpreter_struct_field_write, r#"
+struct Point { x, y }
+
+fn main() {
+  let p = Point { y: 2, x: 1 };
+  p.x = 10;
+  return p;
+}
+"#, Ok(Value::new_struct("Point", vec![
+  ("x".to_string(), Value::Number(10)),
+  ("y".to_string(), Value::Number(2)),
+])));
+test_program!(interpreter_struct_equality, r#"
+struct Point { x, y }
+
+fn main() {
+  let a = Point { x: 1, y: 2 };
+  let b = Point { y: 2, x: 1 };
+  let c = Point { x: 1, y: 3 };
+  return [a == b, a != c, a == c];
+}
+"#, Ok(Value::list(vec![Value::Bool(true), Value::Bool(true), Value::Bool(false)])));
+test_program!(interpreter_struct_unknown_field, r#"
+struct Point { x, y }
+
+fn main() {
+  let p = Point { x: 1, y: 2 };
+  return p.z;
+}
+"#, Err(AsaErrorKind::UnknownField { type_name: "Point".to_string(), field: "z".to_string() }));
+test_program!(interpreter_struct_unknown_initializer, r#"
+struct Point { x, y }
+
+fn main() {
+  return Point { x: 1, y: 2, z: 3 };
+}
+"#, Err(AsaErrorKind::UnknownField { type_name: "Point".to_string(), field: "z".to_string() }));
+test_program!(interpreter_struct_missing_initializer, r#"
+struct Point { x, y }
+
+fn main() {
+  return Point { x: 1 };
+}
+"#, Err(AsaErrorKind::MissingField { type_name: "Point".to_string(), field: "y".to_string() }));
+test_program!(interpreter_struct_undefined, r#"
+fn main() {
+  return Point { x: 1 };
+}
+"#, Err(AsaErrorKind::UndefinedType("Point".to_string())));
+
+// Enums and match
+test_program!(interpreter_match_destructuring, r#"
+enum Shape { Circle(r), Rect(w, h) }
+
+fn main() {
+  let s = Rect(2, 5);
+  return match s {
+    Circle(r) => r,
+    Rect(w, h) => w + h,
+  };
+}
+"#, Ok(Value::Number(7)));
+test_program!(interpreter_match_literals, r#"
+fn name(n) {
+  return match n {
+    0 => "zero",
+    1 => "one",
+    _ => "many",
+  };
+}
+
+fn main() {
+  return [name(0), name(1), name(7)];
+}
+"#, Ok(Value::list(vec![
+  Value::String("zero".to_string()),
+  Value::String("one".to_string()),
+  Value::String("many".to_string()),
+])));
+test_program!(interpreter_match_guard_and_binding, r#"
+fn classify(n) {
+  return match n {
+    x if x > 10 => "big",
+    x => x,
+  };
+}
+
+fn main() {
+  return [classify(20), classify(3)];
+}
+"#, Ok(Value::list(vec![Value::String("big".to_string()), Value::Number(3)])));
+test_program!(interpreter_match_unit_variant, r#"
+enum Option { Some(x), None }
+
+fn unwrap_or(o, default) {
+  return match o {
+    Some(x) => x,
+    None => default,
+  };
+}
+
+fn main() {
+  return [unwrap_or(Some(1), 5), unwrap_or(None, 5)];
+}
+"#, Ok(Value::list(vec![Value::Number(1), Value::Number(5)])));
+test_program!(interpreter_match_recursion, r#"
+fn sum(n) {
+  return match n {
+    0 => 0,
+    _ => n + sum(n - 1),
+  };
+}
+
+fn main() {
+  return sum(4);
+}
+"#, Ok(Value::Number(10)));
+test_program!(interpreter_match_nested_pattern, r#"
+enum Option { Some(x), None }
+
+fn main() {
+  return match Some(Some(3)) {
+    Some(None) => 0,
+    Some(Some(x)) => x,
+    _ => 1,
+  };
+}
+"#, Ok(Value::Number(3)));
+test_fragment!(interpreter_match_non_exhaustive, r#"match 3 { 1 => true }"#, Err(AsaErrorKind::NonExhaustiveMatch(Span::new(1, 1, 1, 21))));
+
+// First-class functions and closures
+test_program!(interpreter_closure_call_result, r#"
+fn make_adder(n) {
+  return fn(x) { x + n };
+}
+
+fn main() {
+  return make_adder(1)(2);
+}
+"#, Ok(Value::Number(3)));
+test_program!(interpreter_closure_in_variable, r#"
+fn make_adder(n) {
+  return fn(x) { x + n };
+}
+
+fn main() {
+  let add_ten = make_adder(10);
+  let add_one = make_adder(1);
+  return [add_ten(5), add_one(5)];
+}
+"#, Ok(Value::list(vec![Value::Number(15), Value::Number(6)])));
+test_program!(interpreter_function_as_argument, r#"
+fn apply(f, x) {
+  return f(x);
+}
+
+fn double(x) {
+  return x + x;
+}
+
+fn main() {
+  return [apply(double, 4), apply(fn(x) { x - 1 }, 4)];
+}
+"#, Ok(Value::list(vec![Value::Number(8), Value::Number(3)])));
+test_program!(interpreter_closure_captures_defining_environment, r#"
+fn make_getter(x) {
+  return fn() { x };
+}
+
+fn main() {
+  let x = 100;
+  let get = make_getter(1);
+  return get();
+}
+"#, Ok(Value::Number(1)));
+test_program!(interpreter_closure_captures_value_at_definition, r#"
+fn main() {
+  let x = 1;
+  let get = fn() { x };
+  let x = 2;
+  return get() + x;
+}
+"#, Ok(Value::Number(3)));
+test_program!(interpreter_call_non_function, r#"
+fn main() {
+  let x = 1;
+  return x(2);
+}
+"#, Err(AsaErrorKind::Generic("Called value is not a function".to_string())));
+
+// Nested function definitions
+test_program!(interpreter_nested_function, r#"
+fn main() {
+  let base = 10;
+  fn add_base(x) {
+    return x + base;
+  }
+  return add_base(5);
+}
+"#, Ok(Value::Number(15)));
+test_program!(interpreter_nested_function_recursion, r#"
+fn main() {
+  fn count(n) {
+    return match n {
+      0 => 0,
+      _ => 1 + count(n - 1),
+    };
+  }
+  return count(5);
+}
+"#, Ok(Value::Number(5)));
+test_program!(interpreter_nested_function_no_collision, r#"
+fn helper() {
+  return 1;
+}
+
+fn other() {
+  fn helper() {
+    return 3;
+  }
+  return helper();
+}
+
+fn main() {
+  fn helper() {
+    return 2;
+  }
+  return [helper(), other()];
+}
+"#, Ok(Value::list(vec![Value::Number(2), Value::Number(3)])));
+test_program!(interpreter_nested_function_global_unchanged, r#"
+fn helper() {
+  return 1;
+}
+
+fn shadow() {
+  fn helper() {
+    return 2;
+  }
+  return helper();
+}
+
+fn main() {
+  let shadowed = shadow();
+  return helper() + shadowed;
+}
+"#, Ok(Value::Number(3)));
+test_program!(interpreter_nested_function_not_visible_outside, r#"
+fn outer() {
+  fn inner() {
+    return 1;
+  }
+  return inner();
+}
+
+fn main() {
+  let x = outer();
+  return inner();
+}
+"#, Err(AsaErrorKind::UndefinedFunctionCall { name: "inner".to_string(), span: Span::new(11, 10, 11, 16) }));
+// The functions of a block can call functions of the block defined after them
+test_program!(interpreter_nested_function_calls_later_function, r#"
+fn main() {
+  fn ev(n) {
+    return od(n);
+  }
+  fn od(n) {
+    return 1;
+  }
+  return ev(4);
+}
+"#, Ok(Value::Number(1)));
+test_program!(interpreter_nested_functions_mutually_recursive, r#"
+fn main() {
+  let yes = true;
+  let no = false;
+  fn is_even(n) {
+    return match n {
+      0 => yes,
+      _ => is_odd(n - 1),
+    };
+  }
+  fn is_odd(n) {
+    return match n {
+      0 => no,
+      _ => is_even(n - 1),
+    };
+  }
+  return [is_even(4), is_odd(7), is_even(3), is_odd(0)];
+}
+"#, Ok(Value::list(vec![Value::Bool(true), Value::Bool(true), Value::Bool(false), Value::Bool(false)])));
+
+// Script semantics tests
+test_fragment!(interpreter_runs_every_top_level_item, r#"let a = 1; let b = a + 1; b + 1"#, Ok(Value::Number(3)));
+test_fragment!(interpreter_top_level_call_statement, r#"
+fn double(x) {
+  return x + x;
+}
+double(1);
+double(4)
+"#, Ok(Value::Number(8)));
+test_fragment!(interpreter_error_after_first_item, r#"let a = 1; b"#, Err(AsaErrorKind::VariableNotDefined { name: "b".to_string(), span: Span::new(1, 12, 1, 12) }));
+test_program!(interpreter_globals_visible_in_functions, r#"
+let limit = 10;
+
+fn main() {
+  return limit + 1;
+}
+"#, Ok(Value::Number(11)));
+test_program!(interpreter_main_result_returned, r#"
+fn main() {
+  return helper();
+}
+let x = 5;
+fn helper() {
+  return x;
+}
+"#, Ok(Value::Number(5)));
+test_program!(interpreter_block_variables_not_global, r#"
+{ let hidden = 1; };
+
+fn main() {
+  return hidden;
+}
+"#, Err(AsaErrorKind::VariableNotDefined { name: "hidden".to_string(), span: Span::new(5, 10, 5, 15) }));
+test_fragment!(interpreter_main_called_by_program, r#"
+let runs = [];
+fn main() {
+  push(runs, 1);
+  return len(runs);
+}
+main()
+"#, Ok(Value::Number(1)));
+test_fragment!(interpreter_main_called_in_function_still_runs, r#"
+let runs = [];
+fn main() {
+  push(runs, 1);
+  return len(runs);
+}
+fn again() {
+  return main();
+}
+len(runs)
+"#, Ok(Value::Number(1)));
+
+// Hoisting tests
+test_program!(interpreter_call_function_defined_later, r#"
+fn main() {
+  return helper(2);
+}
+
+fn helper(x) {
+  return x + 1;
+}
+"#, Ok(Value::Number(3)));
+test_program!(interpreter_mutual_recursion, r#"
+fn is_even(n) {
+  return match n {
+    0 => true,
+    _ => is_odd(n - 1),
+  };
+}
+
+fn is_odd(n) {
+  return match n {
+    0 => false,
+    _ => is_even(n - 1),
+  };
+}
+
+fn main() {
+  return is_even(10) == is_odd(7);
+}
+"#, Ok(Value::Bool(true)));
+test_fragment!(interpreter_top_level_call_before_definition, r#"
+let x = later(4);
+fn later(x) {
+  return x + x;
+}
+x
+"#, Ok(Value::Number(8)));
+test_program!(interpreter_undefined_call_reported_before_execution, r#"
+fn unused() {
+  return missing(1);
+}
+
+fn main() {
+  return 1;
+}
+"#, Err(AsaErrorKind::UndefinedFunctionCall { name: "missing".to_string(), span: Span::new(3, 10, 3, 19) }));
+test_program!(interpreter_call_function_values_checked, r#"
+fn apply(f, x) {
+  return f(x);
+}
+
+fn main() {
+  let double = fn(x) { x + x };
+  return apply(double, 3);
+}
+"#, Ok(Value::Number(6)));
+
+// Module tests
+fn run_with_modules(source: &str, modules: &[(&str, &str)]) -> Result<Value, AsaErrorKind> {
+  let mut loader = MemoryLoader::new();
+  for (path, module_source) in modules {
+    loader.add(path, module_source);
+  }
+  let (tokens, tree) = program(lex(source)).map_err(|e| AsaErrorKind::Generic(format!("{:?}", e)))?;
+  assert!(tokens.is_done());
+  let mut interpreter = Interpreter::with_loader(loader);
+  interpreter.set_path("main.asa");
+  interpreter.exec(&tree)
+}
+
+#[test]
+fn interpreter_import_namespace() {
+  let result = run_with_modules(r#"
+import "math.asa";
+import "util.asa" as u;
+
+fn main() {
+  return math.double(u.inc(2));
+}
+"#, &[
+    ("math.asa", "fn double(x) { return x + x; }"),
+    ("util.asa", "fn inc(x) { return add(x, step); } fn add(a, b) { return a + b; } let step = 1;"),
+  ]);
+  assert_eq!(result, Ok(Value::Number(6)));
+}
+
+#[test]
+fn interpreter_import_relative_path() {
+  let result = run_with_modules(r#"
+import "lib/shapes.asa" as shapes;
+shapes.area(3)
+"#, &[
+    ("lib/shapes.asa", r#"import "../math.asa"; fn area(x) { return math.double(x); }"#),
+    ("math.asa", "fn double(x) { return x + x; }"),
+  ]);
+  assert_eq!(result, Ok(Value::Number(6)));
+}
+
+#[test]
+fn interpreter_import_loaded_once() {
+  let result = run_with_modules(r#"
+import "a.asa";
+import "b.asa";
+push(a.shared.items, 1);
+len(b.shared.items)
+"#, &[
+    ("a.asa", r#"import "shared.asa";"#),
+    ("b.asa", r#"import "shared.asa";"#),
+    ("shared.asa", r#"let items = [];"#),
+  ]);
+  assert_eq!(result, Ok(Value::Number(1)));
+}
+
+#[test]
+fn interpreter_import_cycle() {
+  let result = run_with_modules(r#"import "a.asa";"#, &[
+    ("a.asa", r#"import "b.asa";"#),
+    ("b.asa", r#"import "main.asa";"#),
+  ]);
+  assert_eq!(result, Err(AsaErrorKind::ImportCycle(vec!["main.asa".to_string(), "a.asa".to_string(), "b.asa".to_string(), "main.asa".to_string()])));
+}
+
+#[test]
+fn interpreter_import_not_found() {
+  let result = run_with_modules(r#"import "missing.asa";"#, &[]);
+  assert_eq!(result, Err(AsaErrorKind::ModuleNotFound("missing.asa".to_string())));
+}
+
+#[test]
+fn interpreter_import_callback_runs_in_caller_module() {
+  let result = run_with_modules(r#"
+import "util.asa" as u;
+let offset = 10;
+fn shift(x) {
+  return x + offset;
+}
+u.apply(shift, 1)
+"#, &[
+    ("util.asa", "let offset = 100; fn apply(f, x) { return f(x) + offset; }"),
+  ]);
+  assert_eq!(result, Ok(Value::Number(111)));
+}
+
+// Type checker tests
+test_program!(interpreter_annotated_program, r#"
+struct Point { x, y }
+
+fn shift(p: Point, by: int) -> int {
+  return p.x + by;
+}
+
+fn main() -> int {
+  let p: Point = Point { x: 1, y: 2 };
+  let total: int = shift(p, 2);
+  total
+}
+"#, Ok(Value::Number(3)));
+test_program!(interpreter_type_errors_reported_together, r#"
+fn greet(name: string) -> string {
+  return 1;
+}
+
+fn main() {
+  let count: int = "three";
+  let local = true;
+  return greet(local);
+}
+"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "string".to_string(), found: "int".to_string(), span: Span::new(2, 27, 2, 32) },
+  AsaErrorKind::TypeMismatch { expected: "int".to_string(), found: "string".to_string(), span: Span::new(7, 14, 7, 16) },
+  AsaErrorKind::TypeMismatch { expected: "string".to_string(), found: "bool".to_string(), span: Span::new(9, 10, 9, 21) },
+])));
+test_program!(interpreter_unannotated_code_checked_at_runtime, r#"
+fn inc(x) {
+  return x + 1;
+}
+
+fn main() {
+  let ok = inc(1);
+  return inc(true);
+}
+"#, Err(AsaErrorKind::Generic("MathOperationError".to_string())));
+test_fragment!(interpreter_trailing_expression_return_type, r#"
+fn flag() -> bool {
+  1 + 1
+}
+flag()
+"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "bool".to_string(), found: "int".to_string(), span: Span::new(2, 14, 2, 17) },
+])));
+test_fragment!(interpreter_unknown_type_annotation, r#"let x: widget = 1; x"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::UndefinedTypeAnnotation { name: "widget".to_string(), span: Span::new(1, 8, 1, 13) },
+])));
+// Every annotation is checked once
+test_fragment!(interpreter_unknown_type_in_signature, r#"
+fn f(x: widget) -> widget { x }
+1
+"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::UndefinedTypeAnnotation { name: "widget".to_string(), span: Span::new(2, 9, 2, 14) },
+  AsaErrorKind::UndefinedTypeAnnotation { name: "widget".to_string(), span: Span::new(2, 20, 2, 25) },
+])));
+test_fragment!(interpreter_return_type_missing_value, r#"
+fn f() -> int {
+  let a = 1;
+}
+f()
+"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "int".to_string(), found: "nil".to_string(), span: Span::new(2, 11, 2, 13) },
+])));
+test_fragment!(interpreter_return_type_one_branch, r#"
+fn f(n) -> int {
+  match n {
+    0 => { return 1; },
+    _ => { let skipped = n; },
+  };
+}
+f(1)
+"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "int".to_string(), found: "nil".to_string(), span: Span::new(2, 12, 2, 14) },
+])));
+test_fragment!(interpreter_return_type_every_branch, r#"
+fn f(n) -> int {
+  match n {
+    0 => { return 1; },
+    _ => { throw "not zero"; },
+  };
+}
+f(0)
+"#, Ok(Value::Number(1)));
+// A function can run after any definition of a global, so the type of a redefined global is not known in functions
+test_fragment!(interpreter_redefined_global_in_function, r#"
+let x = 1;
+fn f() -> string { x + "b" }
+let x = "a";
+f()
+"#, Ok(Value::String("ab".to_string())));
+test_fragment!(interpreter_comparison_one_error, r#"true < false"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "int".to_string(), found: "bool".to_string(), span: Span::new(1, 6, 1, 6) },
+])));
+
+// Generic function tests
+test_program!(interpreter_generic_functions, r#"
+fn identity<T>(x: T) -> T {
+  x
+}
+
+fn first<T>(xs: list<T>) -> T {
+  return xs[0];
+}
+
+fn swap<T>(xs: list<T>, i: int, j: int) -> list<T> {
+  let tmp: T = xs[i];
+  xs[i] = xs[j];
+  xs[j] = tmp;
+  xs
+}
+
+fn main() -> int {
+  let word: string = identity("a");
+  let n: int = first(swap([1, 2, 3], 0, 2));
+  n
+}
+"#, Ok(Value::Number(3)));
+test_fragment!(interpreter_generic_return_type_inferred, r#"
+fn first<T>(xs: list<T>) -> T {
+  return xs[0];
+}
+let xs: list<int> = [1, 2];
+let s: string = first(xs);
+"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "string".to_string(), found: "int".to_string(), span: Span::new(6, 8, 6, 13) },
+])));
+test_fragment!(interpreter_generic_conflicting_arguments, r#"
+fn pick<T>(a: T, b: T) -> T {
+  a
+}
+pick(1, "x")
+"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeParameterMismatch { parameter: "T".to_string(), expected: "int".to_string(), found: "string".to_string(), span: Span::new(5, 1, 5, 12) },
+])));
+test_fragment!(interpreter_generic_body_checked_once, r#"
+fn bad<T>(x: T, xs: list<T>) -> int {
+  x + 1
+}
+"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "int".to_string(), found: "T".to_string(), span: Span::new(3, 5, 3, 5) },
+])));
+test_fragment!(interpreter_generic_list_argument_mismatch, r#"
+fn total(xs: list<int>) -> int {
+  len(xs)
+}
+let words: list<string> = ["a", "b"];
+total(words)
+"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "int".to_string(), found: "string".to_string(), span: Span::new(6, 1, 6, 12) },
+])));
+// The elements of unannotated lists and maps can change type, only annotations give them one
+test_fragment!(interpreter_list_literal_element_pushed, r#"let xs = [1]; push(xs, "a"); xs[1] + "b""#, Ok(Value::String("ab".to_string())));
+test_fragment!(interpreter_list_literal_element_replaced, r#"let xs = [1, 2]; xs[0] = "a"; xs[0] + "b""#, Ok(Value::String("ab".to_string())));
+test_fragment!(interpreter_map_literal_value_replaced, r#"let m = {"k": 1}; m["k"] = "v"; m["k"] + "!""#, Ok(Value::String("v!".to_string())));
+test_fragment!(interpreter_concat_mixed_elements, r#"concat([1], ["s"])[1] + "!""#, Ok(Value::String("s!".to_string())));
+test_fragment!(interpreter_string_interpolation, r#"
+let name = "Ada";
+let age = 36;
+"Hello {name}, you are {age + 1}"
+"#, Ok(Value::String("Hello Ada, you are 37".to_string())));
+test_fragment!(interpreter_string_interpolation_display, r#"
+let xs = [1, "two", true];
+let m = {"k": [2]};
+"{xs} {m} {len(xs)}"
+"#, Ok(Value::String(r#"[1, "two", true] {"k": [2]} 3"#.to_string())));
+test_fragment!(interpreter_string_literal_braces, r#""{{x}} = {1 + 2}""#, Ok(Value::String("{x} = 3".to_string())));
+test_program!(interpreter_string_interpolation_scope, r#"
+fn greet(who: string) -> string {
+  let n = 2;
+  "hi {who} x{n}"
+}
+fn main() {
+  greet("bob")
+}
+"#, Ok(Value::String("hi bob x2".to_string())));
+test_fragment!(interpreter_string_concatenation, r#"
+let greeting = "Hello" + ", ";
+greeting + "world"
+"#, Ok(Value::String("Hello, world".to_string())));
+test_fragment!(interpreter_string_equality, r#""a" == "a""#, Ok(Value::Bool(true)));
+test_fragment!(interpreter_string_inequality, r#""a" != "b""#, Ok(Value::Bool(true)));
+test_fragment!(interpreter_string_less_than, r#""apple" < "banana""#, Ok(Value::Bool(true)));
+test_fragment!(interpreter_string_greater_equal, r#""abc" >= "abd""#, Ok(Value::Bool(false)));
+test_fragment!(interpreter_mixed_type_equality, r#"
+let n = 1 == "1";
+let b = true != [true];
+[n, b]
+"#, Ok(Value::list(vec![Value::Bool(false), Value::Bool(true)])));
+test_fragment!(interpreter_collection_equality, r#"
+let xs = [1, {"a": "b"}];
+xs == [1, {"a": "b"}]
+"#, Ok(Value::Bool(true)));
+test_program!(interpreter_untyped_mixed_equality, r#"
+fn same(a, b) {
+  a == b
+}
+fn main() {
+  [same(1, "x"), same("x", "x")]
+}
+"#, Ok(Value::list(vec![Value::Bool(false), Value::Bool(true)])));
+test_fragment!(interpreter_string_minus_error, r#""a" - "b""#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "int".to_string(), found: "string".to_string(), span: Span::new(1, 5, 1, 5) },
+])));
+test_fragment!(interpreter_string_number_concatenation_error, r#""a" + 1"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "string".to_string(), found: "int".to_string(), span: Span::new(1, 5, 1, 5) },
+])));
+test_fragment!(interpreter_string_number_ordering_error, r#""a" < 1"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "string".to_string(), found: "int".to_string(), span: Span::new(1, 5, 1, 5) },
+])));
+test_program!(interpreter_default_parameter, r#"
+fn greet(name, greeting = "hi") {
+  "{greeting} {name}"
+}
+fn main() {
+  [greet("bob"), greet("bob", "yo")]
+}
+"#, Ok(Value::list(vec![Value::String("hi bob".to_string()), Value::String("yo bob".to_string())])));
+test_program!(interpreter_default_evaluated_per_call, r#"
+fn collect(x, xs = []) {
+  push(xs, x);
+  xs
+}
+fn main() {
+  collect(1);
+  collect(2)
+}
+"#, Ok(Value::list(vec![Value::Number(2)])));
+test_program!(interpreter_default_uses_earlier_parameter, r#"
+fn range(from, to = from + 10) {
+  to - from
+}
+fn main() {
+  range(5)
+}
+"#, Ok(Value::Number(10)));
+test_program!(interpreter_named_arguments, r#"
+fn greet(name, greeting = "hi", punctuation = "!") {
+  "{greeting} {name}{punctuation}"
+}
+fn main() {
+  [greet(greeting: "yo", name: "x"), greet("y", punctuation: "?")]
+}
+"#, Ok(Value::list(vec![Value::String("yo x!".to_string()), Value::String("hi y?".to_string())])));
+test_fragment!(interpreter_named_arguments_lambda, r#"
+let sub = fn(a, b) { a - b };
+sub(b: 1, a: 10)
+"#, Ok(Value::Number(9)));
+test_fragment!(interpreter_unknown_named_argument, r#"
+fn f(a) { a }
+f(b: 1)
+"#, Err(AsaErrorKind::UnknownArgument { function: "f".to_string(), argument: "b".to_string() }));
+test_fragment!(interpreter_duplicate_named_argument, r#"
+fn f(a, b) { a }
+f(a: 1, a: 2)
+"#, Err(AsaErrorKind::DuplicateArgument { function: "f".to_string(), argument: "a".to_string() }));
+test_fragment!(interpreter_named_argument_passed_by_position, r#"
+fn f(a, b) { a }
+f(1, a: 2)
+"#, Err(AsaErrorKind::DuplicateArgument { function: "f".to_string(), argument: "a".to_string() }));
+test_fragment!(interpreter_missing_argument, r#"
+fn f(a, b = 2, c) { a }
+f(1, 2)
+"#, Err(AsaErrorKind::MissingArgument { function: "f".to_string(), argument: "c".to_string() }));
+test_fragment!(interpreter_too_many_arguments, r#"
+fn f(a) { a }
+f(1, 2)
+"#, Err(AsaErrorKind::Generic("Function 'f' called with too many arguments. Expected at most 1, got 2".to_string())));
+test_fragment!(interpreter_positional_after_named, r#"
+fn f(a, b) { a }
+f(b: 1, 2)
+"#, Err(AsaErrorKind::PositionalAfterNamed(Span::new(3, 3, 3, 6))));
+test_fragment!(interpreter_named_argument_to_builtin, r#"len(xs: [1])"#, Err(AsaErrorKind::UnknownArgument { function: "len".to_string(), argument: "xs".to_string() }));
+test_fragment!(interpreter_default_type_mismatch, r#"
+fn f(a: int = "x") { a }
+"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "int".to_string(), found: "string".to_string(), span: Span::new(2, 9, 2, 11) },
+])));
+test_fragment!(interpreter_named_argument_type_mismatch, r#"
+fn f(a: int, b: string) { a }
+f(b: 1, a: 2)
+"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "string".to_string(), found: "int".to_string(), span: Span::new(3, 1, 3, 13) },
+])));
+test_program!(interpreter_rest_parameter, r#"
+fn log(level, ...parts) {
+  [level, parts]
+}
+fn main() {
+  [log("info"), log("warn", 1, "two")]
+}
+"#, Ok(Value::list(vec![
+  Value::list(vec![Value::String("info".to_string()), Value::list(vec![])]),
+  Value::list(vec![Value::String("warn".to_string()), Value::list(vec![Value::Number(1), Value::String("two".to_string())])]),
+])));
+test_program!(interpreter_spread_arguments, r#"
+fn triple(a, b, c) {
+  [c, b, a]
+}
+fn main() {
+  let xs = [2, 3];
+  triple(1, ...xs)
+}
+"#, Ok(Value::list(vec![Value::Number(3), Value::Number(2), Value::Number(1)])));
+test_program!(interpreter_spread_into_rest, r#"
+fn count(...xs) {
+  len(xs)
+}
+fn main() {
+  let xs = [1, 2];
+  count(0, ...xs, ...[3, 4], 5)
+}
+"#, Ok(Value::Number(6)));
+test_fragment!(interpreter_rest_with_default, r#"
+fn f(a, b = 10, ...rest) { [a, b, rest] }
+f(1)
+"#, Ok(Value::list(vec![Value::Number(1), Value::Number(10), Value::list(vec![])])));
+test_fragment!(interpreter_spread_into_builtin, r#"len(...[[1, 2, 3]])"#, Ok(Value::Number(3)));
+test_fragment!(interpreter_rest_too_few_arguments, r#"
+fn log(level, ...parts) { parts }
+log()
+"#, Err(AsaErrorKind::MissingArgument { function: "log".to_string(), argument: "level".to_string() }));
+test_fragment!(interpreter_rest_not_named, r#"
+fn log(level, ...parts) { parts }
+log(1, parts: [2])
+"#, Err(AsaErrorKind::RestArgumentByName { function: "log".to_string(), argument: "parts".to_string() }));
+test_fragment!(interpreter_rest_unknown_named, r#"
+fn log(level, ...parts) { parts }
+log(1, part: [2])
+"#, Err(AsaErrorKind::UnknownArgument { function: "log".to_string(), argument: "part".to_string() }));
+test_fragment!(interpreter_spread_non_list, r#"
+fn f(a) { a }
+f(...1)
+"#, Err(AsaErrorKind::Generic("Only lists can be spread into arguments".to_string())));
+test_fragment!(interpreter_rest_argument_type_mismatch, r#"
+fn sum(first: int, ...rest: list<int>) -> int { first }
+sum(1, 2, "3")
+"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "int".to_string(), found: "string".to_string(), span: Span::new(3, 1, 3, 14) },
+])));
+test_fragment!(interpreter_rest_annotation_not_list, r#"
+fn f(...rest: int) { rest }
+"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "list".to_string(), found: "int".to_string(), span: Span::new(2, 15, 2, 17) },
+])));
+test_fragment!(interpreter_math_precedence, r#"2 + 3 * 4 - 6 / 2"#, Ok(Value::Number(11)));
+test_fragment!(interpreter_division_by_zero, r#"
+let zero = 0;
+10 / zero
+"#, Err(AsaErrorKind::DivisionByZero));
+test_program!(interpreter_const_in_function, r#"
+const LIMIT = 10 * 60;
+fn seconds(minutes) {
+  minutes * LIMIT
+}
+fn main() {
+  seconds(2)
+}
+"#, Ok(Value::Number(1200)));
+test_fragment!(interpreter_const_evaluated_first, r#"
+let x = DOUBLE + 1;
+const DOUBLE = BASE * 2;
+const BASE = 3;
+x
+"#, Ok(Value::Number(7)));
+test_fragment!(interpreter_const_pure_builtin, r#"
+const NAMES = ["a", "b", "c"];
+const COUNT = len(NAMES);
+const LABEL = "{COUNT} names";
+LABEL
+"#, Ok(Value::String("3 names".to_string())));
+test_fragment!(interpreter_const_impure_builtin, r#"
+const XS = push([], 1);
+"#, Err(AsaErrorKind::NotConstant("XS".to_string())));
+test_fragment!(interpreter_const_function_call, r#"
+fn f() { 1 }
+const X = f();
+"#, Err(AsaErrorKind::NotConstant("X".to_string())));
+test_fragment!(interpreter_const_refers_to_variable, r#"
+let y = 1;
+const X = y + 1;
+"#, Err(AsaErrorKind::NotConstant("X".to_string())));
+test_fragment!(interpreter_const_cycle, r#"
+const A = B + 1;
+const B = C + 1;
+const C = A;
+"#, Err(AsaErrorKind::ConstantCycle(vec!["A".to_string(), "B".to_string(), "C".to_string(), "A".to_string()])));
+test_fragment!(interpreter_const_self_reference, r#"const A = A + 1;"#, Err(AsaErrorKind::ConstantCycle(vec!["A".to_string(), "A".to_string()])));
+test_program!(interpreter_const_assignment, r#"
+const XS = [1, 2];
+fn main() {
+  XS[0] = 3;
+}
+"#, Err(AsaErrorKind::AssignmentToConstant("XS".to_string())));
+test_program!(interpreter_const_shadowed_assignment, r#"
+const XS = [1, 2];
+fn main() {
+  let XS = [0];
+  XS[0] = 3;
+  XS
+}
+"#, Ok(Value::list(vec![Value::Number(3)])));
+test_fragment!(interpreter_const_redefined, r#"
+const A = 1;
+let A = 2;
+"#, Err(AsaErrorKind::ConstantRedefined("A".to_string())));
+test_fragment!(interpreter_const_defined_twice, r#"
+const A = 1;
+const A = 2;
+"#, Err(AsaErrorKind::ConstantRedefined("A".to_string())));
+test_fragment!(interpreter_const_redefined_by_function, r#"
+const A = 1;
+fn A() { 2 }
+"#, Err(AsaErrorKind::ConstantRedefined("A".to_string())));
+// Constants are shallow, the list can change through another variable holding it
+test_program!(interpreter_const_shallow, r#"
+const XS = [1, 2];
+fn main() {
+  let alias = XS;
+  alias[0] = 3;
+  push(alias, 4);
+  XS
+}
+"#, Ok(Value::list(vec![Value::Number(3), Value::Number(2), Value::Number(4)])));
+test_fragment!(interpreter_const_type_mismatch, r#"const A: string = 1;"#, Err(AsaErrorKind::TypeErrors(vec![
+  AsaErrorKind::TypeMismatch { expected: "string".to_string(), found: "int".to_string(), span: Span::new(1, 10, 1, 15) },
+])));
+test_fragment!(interpreter_catch_runtime_error, r#"
+let zero = 0;
+try { 10 / zero } catch (e) { [e.kind, e.message] }
+"#, Ok(Value::list(vec![Value::String("DivisionByZero".to_string()), Value::String("division by zero".to_string())])));
+// An undefined variable in the body of a try fails when it is read, so it can be caught
+test_fragment!(interpreter_catch_undefined_variable, r#"
+try { missing + 1 } catch (e) { [e.kind, e.message] }
+"#, Ok(Value::list(vec![Value::String("VariableNotDefined".to_string()), Value::String("variable 'missing' is not defined".to_string())])));
+test_fragment!(interpreter_undefined_variable_in_handler, r#"try { 1 } catch (e) { missing }"#,
+  Err(AsaErrorKind::VariableNotDefined { name: "missing".to_string(), span: Span::new(1, 23, 1, 29) }));
+test_fragment!(interpreter_catch_index_error, r#"
+let xs = [1];
+try { xs[3] } catch (e) { e.message }
+"#, Ok(Value::String("index 3 out of bounds for length 1".to_string())));
+test_fragment!(interpreter_catch_thrown_value, r#"
+try {
+  throw {"code": 42};
+} catch (e) {
+  e["code"]
+}
+"#, Ok(Value::Number(42)));
+test_fragment!(interpreter_try_without_error, r#"try { 1 } catch (e) { 2 }"#, Ok(Value::Number(1)));
+test_program!(interpreter_throw_through_calls, r#"
+fn check(n) {
+  match n > 10 {
+    true => { throw "too big: {n}"; },
+    false => n,
+  }
+}
+fn main() {
+  let small = check(3);
+  let big = try { check(11) } catch (e) { e };
+  [small, big]
+}
+"#, Ok(Value::list(vec![Value::Number(3), Value::String("too big: 11".to_string())])));
+test_fragment!(interpreter_finally_runs, r#"
+let log = [];
+let ok = try { push(log, "body"); 1 } catch (e) { 2 } finally { push(log, "finally"); };
+let failed = try { throw 0; } catch (e) { push(log, "catch"); 3 } finally { push(log, "finally"); };
+[ok, failed, log]
+"#, Ok(Value::list(vec![
+  Value::Number(1),
+  Value::Number(3),
+  Value::list(vec![
+    Value::String("body".to_string()),
+    Value::String("finally".to_string()),
+    Value::String("catch".to_string()),
+    Value::String("finally".to_string()),
+  ]),
+])));
+test_fragment!(interpreter_finally_runs_on_rethrow, r#"
+let log = [];
+let outer = try {
+  try { throw 1; } catch (e) { throw e + 1; } finally { push(log, "inner"); }
+} catch (e) {
+  e
+};
+[outer, log]
+"#, Ok(Value::list(vec![Value::Number(2), Value::list(vec![Value::String("inner".to_string())])])));
+test_program!(interpreter_scope_restored_after_catch, r#"
+fn divide(a, b) {
+  let q = a / b;
+  q
+}
+fn main() {
+  let q = "outer";
+  let r = try { divide(1, 0) } catch (e) { q };
+  [r, q]
+}
+"#, Ok(Value::list(vec![Value::String("outer".to_string()), Value::String("outer".to_string())])));
+test_fragment!(interpreter_uncaught_throw, r#"throw "boom";"#, Err(AsaErrorKind::Thrown(Value::String("boom".to_string()))));
+test_fragment!(interpreter_uncaught_runtime_error_in_catch, r#"
+let zero = 0;
+try { throw 1; } catch (e) { e / zero }
+"#, Err(AsaErrorKind::DivisionByZero));
+// Nil
+test_fragment!(interpreter_nil_literal, r#"nil"#, Ok(Value::Nil));
+test_fragment!(interpreter_nil_is_not_false, r#"[nil == nil, nil == false, nil != true]"#, Ok(Value::list(vec![
+  Value::Bool(true),
+  Value::Bool(false),
+  Value::Bool(true),
+])));
+test_program!(interpreter_empty_body_is_nil, r#"
+fn nothing() {}
+fn main() { nothing() }
+"#, Ok(Value::Nil));
+test_program!(interpreter_function_without_value_is_nil, r#"
+fn log(xs) {
+  xs[0] = 1;
+}
+fn main() {
+  let xs = [0];
+  [log(xs), xs]
+}
+"#, Ok(Value::list(vec![Value::Nil, Value::list(vec![Value::Number(1)])])));
+test_program!(interpreter_definitions_are_nil, r#"
+fn main() {
+  fn helper() { 1 }
+}
+"#, Ok(Value::Nil));
+test_fragment!(interpreter_nil_displayed, r#""value: {nil}, list: {[nil]}""#, Ok(Value::String("value: nil, list: [nil]".to_string())));
+// Early return
+test_program!(interpreter_statements_after_return_do_not_run, r#"
+fn f(log) {
+  push(log, "before");
+  return 1;
+  push(log, "after");
+  2
+}
+fn main() {
+  let log = [];
+  [f(log), log]
+}
+"#, Ok(Value::list(vec![Value::Number(1), Value::list(vec![Value::String("before".to_string())])])));
+test_program!(interpreter_return_from_match_arm, r#"
+fn sign(n, log) {
+  match n {
+    0 => { return "zero"; },
+    _ => {},
+  };
+  push(log, n);
+  "nonzero"
+}
+fn main() {
+  let log = [];
+  [sign(0, log), sign(5, log), log]
+}
+"#, Ok(Value::list(vec![
+  Value::String("zero".to_string()),
+  Value::String("nonzero".to_string()),
+  Value::list(vec![Value::Number(5)]),
+])));
+test_program!(interpreter_return_from_nested_blocks, r#"
+fn f(log) {
+  let x = {
+    { return 1; };
+    push(log, "inner");
+  };
+  push(log, "outer");
+  2
+}
+fn main() {
+  let log = [];
+  [f(log), log]
+}
+"#, Ok(Value::list(vec![Value::Number(1), Value::list(vec![])])));
+test_program!(interpreter_return_from_argument, r#"
+fn g(log) {
+  push(log, { return "early"; });
+  "late"
+}
+fn main() {
+  let log = [];
+  [g(log), log]
+}
+"#, Ok(Value::list(vec![Value::String("early".to_string()), Value::list(vec![])])));
+test_program!(interpreter_return_from_lambda_returns_lambda_only, r#"
+fn main() {
+  let f = fn() { return 1; 2 };
+  let x = f();
+  [x, 3]
+}
+"#, Ok(Value::list(vec![Value::Number(1), Value::Number(3)])));
+test_program!(interpreter_return_through_try_runs_finally, r#"
+fn f(log) {
+  try {
+    return "body";
+  } catch (e) {
+    "handler"
+  } finally {
+    push(log, "finally");
+  };
+  push(log, "after");
+  "end"
+}
+fn main() {
+  let log = [];
+  [f(log), log]
+}
+"#, Ok(Value::list(vec![Value::String("body".to_string()), Value::list(vec![Value::String("finally".to_string())])])));
+test_program!(interpreter_return_in_finally_replaces_result, r#"
+fn f() {
+  try { throw 1; } catch (e) { return "handler"; } finally { return "finally"; }
+}
+fn main() { f() }
+"#, Ok(Value::String("finally".to_string())));
+test_program!(interpreter_recursive_early_return, r#"
+fn fact(n) {
+  match n {
+    0 => { return 1; },
+    _ => {},
+  };
+  return n * fact(n - 1);
+}
+fn main() { fact(5) }
+"#, Ok(Value::Number(120)));
+test_fragment!(interpreter_return_outside_function, r#"return 1;"#, Err(AsaErrorKind::Generic("Invalid return".to_string())));
+// Recursion and the call depth limit
+test_program!(interpreter_recursive_fib, r#"
+fn fib(n) {
+  match n < 2 {
+    true => n,
+    false => fib(n - 1) + fib(n - 2),
+  }
+}
+fn main() { fib(15) }
+"#, Ok(Value::Number(610)));
+test_program!(interpreter_recursive_fact, r#"
+fn fact(n) {
+  match n {
+    0 => 1,
+    _ => n * fact(n - 1),
+  }
+}
+fn main() { fact(10) }
+"#, Ok(Value::Number(3628800)));
+
+fn run_with_max_depth(source: &str, depth: usize) -> Result<Value, AsaErrorKind> {
+  let (tokens, tree) = program(lex(source)).map_err(|e| AsaErrorKind::Generic(format!("{:?}", e)))?;
+  assert!(tokens.is_done());
+  let mut interpreter = Interpreter::new();
+  interpreter.set_max_depth(depth);
+  interpreter.exec(&tree)
+}
+
+const COUNTDOWN: &str = r#"
+fn down(n) {
+  match n {
+    0 => 0,
+    _ => down(n - 1),
+  }
+}
+fn main() { down(4) }
+"#;
+
+#[test]
+fn interpreter_call_depth_at_limit() {
+  // main and five calls of down
+  assert_eq!(run_with_max_depth(COUNTDOWN, 6), Ok(Value::Number(0)));
+}
+
+#[test]
+fn interpreter_call_depth_exceeded() {
+  let chain = ["main", "down", "down", "down", "down", "down"].iter().map(|name| name.to_string()).collect();
+  assert_eq!(run_with_max_depth(COUNTDOWN, 5), Err(AsaErrorKind::StackOverflow { function: "down".to_string(), chain }));
+}
+
+#[test]
+fn interpreter_call_depth_lambda() {
+  let result = run_with_max_depth(r#"
+fn main() {
+  let f = fn(g) { g(g) };
+  f(f)
+}
+"#, 3);
+  let chain = ["main", "<lambda>", "<lambda>", "<lambda>"].iter().map(|name| name.to_string()).collect();
+  assert_eq!(result, Err(AsaErrorKind::StackOverflow { function: "<lambda>".to_string(), chain }));
+}
+
+#[test]
+fn interpreter_call_depth_overflow_caught() {
+  let result = run_with_max_depth(r#"
+fn forever(n) { forever(n + 1) }
+fn main() {
+  let kind = try { forever(0) } catch (e) { e.kind };
+  [kind, forever2(3)]
+}
+fn forever2(n) {
+  match n {
+    0 => "recovered",
+    _ => forever2(n - 1),
+  }
+}
+"#, 10);
+  assert_eq!(result, Ok(Value::list(vec![
+    Value::String("StackOverflow".to_string()),
+    Value::String("recovered".to_string()),
+  ])));
+}
+
+// Runs a program with the default limits on a thread with the stack size they need. Values cannot leave the
+// thread, so the result is the display form of the value, or the kind of the error followed by the function
+// that exceeded the maximum depth.
+fn run_on_thread(source: &str) -> Result<String, String> {
+  let source = source.to_string();
+  std::thread::Builder::new().stack_size(DEFAULT_STACK_SIZE).spawn(move || {
+    let (_, tree) = program(lex(&source)).unwrap();
+    match Interpreter::new().exec(&tree) {
+      Ok(value) => Ok(value.to_string()),
+      Err(AsaErrorKind::StackOverflow { function, .. }) => Err(format!("StackOverflow in {}", function)),
+      Err(error) => Err(error.kind().to_string()),
+    }
+  }).unwrap().join().unwrap()
+}
+
+// Runaway recursion with the default limits fails with an error instead of overflowing the Rust stack,
+// also when every call is nested in blocks and operators
+#[test]
+fn interpreter_call_depth_default_limit() {
+  let result = run_on_thread("fn forever(n) { forever(n + 1) } fn main() { forever(0) }");
+  assert_eq!(result, Err("StackOverflow in forever".to_string()));
+  let result = run_on_thread(r#"
+fn nested(n) {
+  { { { match n { 0 => 0, _ => 1 + { [nested(n + 1)][0] } } } } }
+}
+fn main() { nested(1) }
+"#);
+  assert_eq!(result, Err("StackOverflow in nested".to_string()));
+  let result = run_on_thread(r#"
+fn interpolated(n) {
+  return 1 + interpolated(n + 1) * 2 - len("{interpolated(n)}");
+}
+fn main() { interpolated(1) }
+"#);
+  assert_eq!(result, Err("StackOverflow in interpolated".to_string()));
+}
+
+// Recursion that is not a tail call runs up to the maximum depth with the default limits
+#[test]
+fn interpreter_call_depth_default_factorial() {
+  let result = run_on_thread(r#"
+fn fact(n) {
+  match n {
+    0 => 1n,
+    _ => n * fact(n - 1),
+  }
+}
+fn main() { fact(100) }
+"#);
+  assert_eq!(result, Ok("93326215443944152681699238856266700490715968264381621468592963895217599993229915608941463976156518286253697920827223758251185210916864000000000000000000000000".to_string()));
+  let result = run_on_thread(r#"
+fn fact(n) {
+  return match n {
+    0 => 1n,
+    _ => n * fact(n - 1),
+  };
+}
+fn main() { fact(100) }
+"#);
+  assert!(result.is_ok(), "{:?}", result);
+}
+
+const LIST_SUM: &str = r#"
+fn fill(n, xs) {
+  match n {
+    0 => { return xs; },
+    _ => {},
+  };
+  push(xs, n);
+  return fill(n - 1, xs);
+}
+fn sum(xs, i) {
+  match i == len(xs) {
+    true => 0,
+    false => xs[i] + sum(xs, i + 1),
+  }
+}
+fn main() { sum(fill(LENGTH, []), 0) }
+"#;
+
+// The sum of n elements runs main and n + 1 calls of sum
+#[test]
+fn interpreter_call_depth_default_list_sum() {
+  assert_eq!(run_on_thread(&LIST_SUM.replace("LENGTH", "400")), Ok("80200".to_string()));
+  let length = DEFAULT_MAX_DEPTH - 2;
+  let result = run_on_thread(&LIST_SUM.replace("LENGTH", &length.to_string()));
+  assert_eq!(result, Ok((length * (length + 1) / 2).to_string()));
+  let result = run_on_thread(&LIST_SUM.replace("LENGTH", &(length + 1).to_string()));
+  assert_eq!(result, Err("StackOverflow in sum".to_string()));
+}
+
+#[test]
+fn interpreter_nesting_exceeded() {
+  let (_, tree) = program(lex("let x = { { { { 1 } } } };")).unwrap();
+  let mut interpreter = Interpreter::new();
+  interpreter.set_max_nesting(4);
+  assert_eq!(interpreter.exec(&tree), Err(AsaErrorKind::StackOverflow { function: "<top level>".to_string(), chain: vec![] }));
+  interpreter.set_max_nesting(DEFAULT_MAX_NESTING);
+  assert_eq!(interpreter.exec(&tree), Ok(Value::Nil));
+}
+
+// Tail calls
+// A million calls run with room for only main and one more frame, so neither the frames of the interpreter
+// nor the Rust stack grow with the number of calls.
+#[test]
+fn interpreter_tail_call_constant_space() {
+  let result = run_with_max_depth(r#"
+fn count(n, acc) {
+  match n {
+    0 => { return acc; },
+    _ => {},
+  };
+  return count(n - 1, acc + 1);
+}
+fn main() { count(1000000, 0) }
+"#, 2);
+  assert_eq!(result, Ok(Value::Number(1000000)));
+}
+
+#[test]
+fn interpreter_tail_call_mutual_recursion() {
+  let result = run_with_max_depth(r#"
+fn is_even(n) {
+  match n {
+    0 => { return true; },
+    _ => {},
+  };
+  return is_odd(n - 1);
+}
+fn is_odd(n) {
+  match n {
+    0 => { return false; },
+    _ => {},
+  };
+  return is_even(n - 1);
+}
+fn main() { [is_even(10000), is_odd(7)] }
+"#, 2);
+  assert_eq!(result, Ok(Value::list(vec![Value::Bool(true), Value::Bool(true)])));
+}
+
+#[test]
+fn interpreter_tail_call_lambda() {
+  let result = run_with_max_depth(r#"
+fn main() {
+  let loop = fn(self, n) {
+    match n {
+      0 => { return "done"; },
+      _ => {},
+    };
+    return self(self, n - 1);
+  };
+  loop(loop, 5000)
+}
+"#, 2);
+  assert_eq!(result, Ok(Value::String("done".to_string())));
+}
+
+// Calls that are not returned still take a frame each
+#[test]
+fn interpreter_non_tail_call_uses_depth() {
+  let result = run_with_max_depth(r#"
+fn total(n) {
+  match n {
+    0 => { return 0; },
+    _ => {},
+  };
+  return 1 + total(n - 1);
+}
+fn main() { total(10) }
+"#, 5);
+  assert!(matches!(result, Err(AsaErrorKind::StackOverflow { .. })));
+}
+
+test_program!(interpreter_tail_call_builtin, r#"
+fn size(xs) { return len(xs); }
+fn main() { size([1, 2, 3]) }
+"#, Ok(Value::Number(3)));
+test_program!(interpreter_tail_call_inside_try_is_caught, r#"
+fn fail() { throw "bad"; }
+fn attempt() {
+  try { return fail(); } catch (e) { "caught " + e }
+}
+fn main() { attempt() }
+"#, Ok(Value::String("caught bad".to_string())));
+test_program!(interpreter_tail_call_from_handler_runs_before_finally, r#"
+fn note(log, entry) { push(log, entry); entry }
+fn attempt(log) {
+  try { throw 1; } catch (e) { return note(log, "handler"); } finally { note(log, "finally"); }
+}
+fn main() {
+  let log = [];
+  [attempt(log), log]
+}
+"#, Ok(Value::list(vec![
+  Value::String("handler".to_string()),
+  Value::list(vec![Value::String("handler".to_string()), Value::String("finally".to_string())]),
+])));
+
+// Name resolution
+// Undefined names are reported before the program runs, also in functions that are never called
+test_program!(interpreter_undefined_variable_in_uncalled_function, r#"
+fn unused() { return missing; }
+fn main() { 1 }
+"#, Err(AsaErrorKind::VariableNotDefined { name: "missing".to_string(), span: Span::new(2, 22, 2, 28) }));
+test_program!(interpreter_variable_used_before_definition, r#"
+fn main() {
+  let y = x;
+  let x = 1;
+  y
+}
+"#, Err(AsaErrorKind::VariableNotDefined { name: "x".to_string(), span: Span::new(3, 11, 3, 11) }));
+test_program!(interpreter_call_of_variable_defined_later, r#"
+fn main() {
+  let f = fn() { g() };
+  let g = fn() { 1 };
+  f()
+}
+"#, Err(AsaErrorKind::UndefinedFunctionCall { name: "g".to_string(), span: Span::new(3, 18, 3, 20) }));
+// A global exists in every function, but is only set when its definition runs
+test_fragment!(interpreter_global_read_before_definition, r#"
+fn read() { x }
+let y = read();
+let x = 1;
+"#, Err(AsaErrorKind::VariableNotDefined { name: "x".to_string(), span: Span::new(2, 13, 2, 13) }));
+test_program!(interpreter_sibling_blocks_share_slots, r#"
+fn main() {
+  let x = 1;
+  { let a = 10; a };
+  let y = { let b = 20; b };
+  [x, y]
+}
+"#, Ok(Value::list(vec![Value::Number(1), Value::Number(20)])));
+test_program!(interpreter_catch_name_shadows_variable, r#"
+fn main() {
+  let e = 1;
+  let caught = try { throw 2; } catch (e) { e };
+  [e, caught]
+}
+"#, Ok(Value::list(vec![Value::Number(1), Value::Number(2)])));
+test_program!(interpreter_capture_through_nested_lambdas, r#"
+fn main() {
+  let a = 1;
+  let outer = fn() {
+    let b = 2;
+    fn() { a + b }
+  };
+  let inner = outer();
+  inner()
+}
+"#, Ok(Value::Number(3)));
+test_program!(interpreter_local_function_recursion_with_capture, r#"
+fn main() {
+  let step = 2;
+  fn count(n) {
+    match n {
+      0 => 0,
+      _ => step + count(n - 1),
+    }
+  }
+  count(3)
+}
+"#, Ok(Value::Number(6)));
+
+// The variables of a resolved program, in the order they appear in the tree
+fn resolved_variables(source: &str) -> Vec<(String, Binding)> {
+  let (_, tree) = program(lex(source)).unwrap();
+  let tree = resolve(&tree, &mut vec![], &|_| false, &|_| false, &|_| false).unwrap();
+  let mut variables = vec![];
+  let mut nodes = vec![&tree];
+  while let Some(node) = nodes.pop() {
+    if let Node::Variable { name, binding, .. } = node {
+      variables.push((String::from_utf8_lossy(name).to_string(), *binding));
+    }
+    nodes.extend(node.children().iter().rev());
+  }
+  variables
+}
+
+#[test]
+fn resolver_binds_slots() {
+  let variables = resolved_variables(r#"
+let g = 1;
+fn f(a) {
+  let b = a + g;
+  fn() { b }
+}
+"#);
+  assert_eq!(variables, vec![
+    ("g".to_string(), Binding::Global(0)),
+    // The function itself is in slot 0, the parameter in slot 1
+    ("b".to_string(), Binding::Local(2)),
+    ("a".to_string(), Binding::Local(1)),
+    ("g".to_string(), Binding::Global(0)),
+    // The lambda reads b from its environment, which is filled from the frame of f
+    ("b".to_string(), Binding::Captured(0)),
+    ("b".to_string(), Binding::Local(2)),
+  ]);
+}
+
+// Integer overflow
+test_fragment!(interpreter_addition_overflow, r#"2147483647 + 1"#, Err(AsaErrorKind::NumberOverflow(Span::new(1, 12, 1, 12))));
+test_fragment!(interpreter_subtraction_underflow, r#"0 - 2147483647 - 2"#, Err(AsaErrorKind::NumberUnderflow(Span::new(1, 16, 1, 16))));
+test_fragment!(interpreter_multiplication_overflow, r#"65536 * 65536"#, Err(AsaErrorKind::NumberOverflow(Span::new(1, 7, 1, 7))));
+test_fragment!(interpreter_division_overflow, r#"let min = 0 - 2147483647 - 1; let minus_one = 0 - 1; min / minus_one"#, Err(AsaErrorKind::NumberOverflow(Span::new(1, 58, 1, 58))));
+test_fragment!(interpreter_largest_number, r#"2147483646 + 1"#, Ok(Value::Number(i32::MAX)));
+test_fragment!(interpreter_catch_overflow, r#"
+try { 2147483647 * 2 } catch (e) { [e.kind, e.message] }
+"#, Ok(Value::list(vec![Value::String("NumberOverflow".to_string()), Value::String("number overflow".to_string())])));
+
+fn run_with_arithmetic(source: &str, arithmetic: Arithmetic) -> Result<Value, AsaErrorKind> {
+  let (tokens, tree) = program(lex(source)).map_err(|e| AsaErrorKind::Generic(format!("{:?}", e)))?;
+  assert!(tokens.is_done());
+  let mut interpreter = Interpreter::new();
+  interpreter.set_arithmetic(arithmetic);
+  interpreter.exec(&tree)
+}
+
+const OVERFLOWS: &str = r#"
+let min = 0 - 2147483647 - 1;
+let minus_one = 0 - 1;
+[2147483647 + 1, min - 1, 65536 * 65536, min / minus_one]
+"#;
+
+#[test]
+fn interpreter_wrapping_arithmetic() {
+  assert_eq!(run_with_arithmetic(OVERFLOWS, Arithmetic::Wrapping), Ok(Value::list(vec![
+    Value::Number(i32::MIN),
+    Value::Number(i32::MAX),
+    Value::Number(0),
+    Value::Number(i32::MIN),
+  ])));
+}
+
+#[test]
+fn interpreter_saturating_arithmetic() {
+  assert_eq!(run_with_arithmetic(OVERFLOWS, Arithmetic::Saturating), Ok(Value::list(vec![
+    Value::Number(i32::MAX),
+    Value::Number(i32::MIN),
+    Value::Number(i32::MAX),
+    Value::Number(i32::MAX),
+  ])));
+}
+
+// A number literal that is too large is handled like the result of an operator
+test_fragment!(interpreter_number_literal_overflow, r#"let x = 1 + 2147483648;"#, Err(AsaErrorKind::NumberOverflow(Span::new(1, 13, 1, 22))));
+
+#[test]
+fn interpreter_large_number_literal() {
+  let literals = "[2147483648, 4294967297]";
+  assert_eq!(run_with_arithmetic(literals, Arithmetic::Wrapping), Ok(Value::list(vec![Value::Number(i32::MIN), Value::Number(1)])));
+  assert_eq!(run_with_arithmetic(literals, Arithmetic::Saturating), Ok(Value::list(vec![Value::Number(i32::MAX), Value::Number(i32::MAX)])));
+  assert_eq!(run_with_arithmetic(literals, Arithmetic::Promoting), Ok(Value::list(vec![big("2147483648"), big("4294967297")])));
+}
+
+#[test]
+fn interpreter_arithmetic_mode_keeps_division_by_zero() {
+  assert_eq!(run_with_arithmetic("1 / 0", Arithmetic::Saturating), Err(AsaErrorKind::DivisionByZero));
+}
+
+// Big integers
+fn big(text: &str) -> Value {
+  Value::BigInt(text.parse().unwrap())
+}
+
+test_fragment!(interpreter_bigint_literal, r#"12345678901234567890n"#, Ok(big("12345678901234567890")));
+test_fragment!(interpreter_bigint_displayed, r#""{12345678901234567890n} {[0n - 1n]}""#, Ok(Value::String("12345678901234567890 [-1]".to_string())));
+test_program!(interpreter_bigint_factorial, r#"
+fn fact(n) {
+  match n {
+    0 => 1n,
+    _ => n * fact(n - 1),
+  }
+}
+fn main() { [fact(25), fact(30)] }
+"#, Ok(Value::list(vec![big("15511210043330985984000000"), big("265252859812191058636308480000000")])));
+test_fragment!(interpreter_bigint_operators, r#"
+let large = 100000000000000000000n;
+let minus_two = 0 - 2;
+[large + 1, large - 1, 0 - large, large * large, large / 3, 7n / minus_two]
+"#, Ok(Value::list(vec![
+  big("100000000000000000001"),
+  big("99999999999999999999"),
+  big("-100000000000000000000"),
+  big("10000000000000000000000000000000000000000"),
+  big("33333333333333333333"),
+  big("-3"),
+])));
+test_fragment!(interpreter_bigint_comparisons, r#"
+[1n == 1, 3 != 3n, 2147483648n > 2147483647, 5 < 6n, 0 - 10n <= 0 - 10, [1n] == [1n]]
+"#, Ok(Value::list(vec![
+  Value::Bool(true),
+  Value::Bool(false),
+  Value::Bool(true),
+  Value::Bool(true),
+  Value::Bool(true),
+  Value::Bool(true),
+])));
+// Numbers and big integers are compared by value inside containers too
+test_fragment!(interpreter_bigint_equal_in_containers, r#"
+[[1n] == [1], {"a": [2n]} == {"a": [2]}, [1n] != [1], [1n] == [2]]
+"#, Ok(Value::list(vec![Value::Bool(true), Value::Bool(true), Value::Bool(false), Value::Bool(false)])));
+
+// Values of the host are equal only with the same representation
+#[test]
+fn interpreter_value_equality_is_strict() {
+  assert_ne!(Value::list(vec![big("1")]), Value::list(vec![Value::Number(1)]));
+}
+
+test_fragment!(interpreter_bigint_index, r#"
+let xs = [10, 20, 30];
+xs[2n] = 40;
+[xs[1n], slice(xs, 0n, 2n), xs]
+"#, Ok(Value::list(vec![
+  Value::Number(20),
+  Value::list(vec![Value::Number(10), Value::Number(20)]),
+  Value::list(vec![Value::Number(10), Value::Number(20), Value::Number(40)]),
+])));
+test_fragment!(interpreter_bigint_index_out_of_bounds, r#"
+let xs = [10];
+xs[3n]
+"#, Err(AsaErrorKind::IndexOutOfBounds { index: 3, length: 1 }));
+test_fragment!(interpreter_large_bigint_index, r#"
+let xs = [10];
+xs[99999999999n]
+"#, Err(AsaErrorKind::LargeIndexOutOfBounds { index: "99999999999".parse().unwrap(), length: 1 }));
+test_fragment!(interpreter_large_bigint_index_caught, r#"
+let xs = [10];
+try { xs[99999999999n] } catch (e) { [e.kind, e.message] }
+"#, Ok(Value::list(vec![
+  Value::String("IndexOutOfBounds".to_string()),
+  Value::String("index 99999999999 out of bounds for length 1".to_string()),
+])));
+test_fragment!(interpreter_bigint_matches_number_pattern, r#"
+match 5n { 5 => "five", _ => "other" }
+"#, Ok(Value::String("five".to_string())));
+test_fragment!(interpreter_bigint_division_by_zero, r#"1n / 0"#, Err(AsaErrorKind::DivisionByZero));
+
+const POWER: &str = r#"
+fn power(base, exponent, result) {
+  match exponent {
+    0 => { return result; },
+    _ => {},
+  };
+  return power(base, exponent - 1, result * base);
+}
+fn main() { power(2, 100, 1) }
+"#;
+
+#[test]
+fn interpreter_promoting_arithmetic() {
+  assert_eq!(run_with_arithmetic(POWER, Arithmetic::Promoting), Ok(big("1267650600228229401496703205376")));
+  assert_eq!(run_with_arithmetic("let next = 2147483647 + 1; [next, next - 1 == 2147483647]", Arithmetic::Promoting), Ok(Value::list(vec![
+    big("2147483648"),
+    Value::Bool(true),
+  ])));
+}
+
+#[test]
+fn interpreter_checked_arithmetic_does_not_promote() {
+  assert!(matches!(run_with_arithmetic(POWER, Arithmetic::Checked), Err(AsaErrorKind::NumberOverflow(_))));
+}
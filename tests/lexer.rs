@@ -160,4 +160,117 @@ fn lexer_test_less_than() {
 #[test]
 fn lexer_test_not_equal_to() {
   assert_eq!(test_lex("a != b"),vec![TokenKind::Alpha, TokenKind::NotEqualTo, TokenKind::Alpha, TokenKind::EOF]);
-}
\ No newline at end of file
+}
+#[test]
+fn lexer_test_brackets() {
+  assert_eq!(test_lex("xs[1]"),vec![TokenKind::Alpha, TokenKind::Alpha, TokenKind::LeftBracket, TokenKind::Digit, TokenKind::RightBracket, TokenKind::EOF]);
+}
+
+#[test]
+fn lexer_test_struct() {
+  assert_eq!(test_lex("struct P {x} p.x"),vec![
+    TokenKind::Struct,
+    TokenKind::Alpha,
+    TokenKind::LeftCurly,
+    TokenKind::Alpha,
+    TokenKind::RightCurly,
+    TokenKind::Alpha,
+    TokenKind::Dot,
+    TokenKind::Alpha,
+    TokenKind::EOF,
+  ]);
+}
+
+#[test]
+fn lexer_test_keyword_inside_identifier() {
+  let mut expected = vec![TokenKind::Alpha; 9];
+  expected.push(TokenKind::EOF);
+  assert_eq!(test_lex("construct"),expected);
+}
+
+#[test]
+fn lexer_test_match() {
+  assert_eq!(test_lex("match x { _ => 1 }"),vec![
+    TokenKind::Match,
+    TokenKind::Alpha,
+    TokenKind::LeftCurly,
+    TokenKind::Underscore,
+    TokenKind::FatArrow,
+    TokenKind::Digit,
+    TokenKind::RightCurly,
+    TokenKind::EOF,
+  ]);
+}
+
+#[test]
+fn lexer_test_columns() {
+  let tokens = lex("return x >= 1");
+  let columns: Vec<(u32, u32)> = tokens.tokens.iter().map(|t| (t.start_col, t.end_col)).collect();
+  assert_eq!(columns, vec![(1, 6), (8, 8), (10, 11), (13, 13), (14, 14)]);
+}
+
+#[test]
+fn lexer_test_arrow() {
+  assert_eq!(test_lex("a->b - c"),vec![TokenKind::Alpha, TokenKind::Arrow, TokenKind::Alpha, TokenKind::Dash, TokenKind::Alpha, TokenKind::EOF]);
+}
+
+#[test]
+fn lexer_test_string_interpolation() {
+  assert_eq!(test_lex(r#""a {x + 1} {{""#),vec![
+    TokenKind::Quote,
+    TokenKind::Alpha,
+    TokenKind::WhiteSpace,
+    TokenKind::LeftCurly,
+    TokenKind::Alpha,
+    TokenKind::Plus,
+    TokenKind::Digit,
+    TokenKind::RightCurly,
+    TokenKind::WhiteSpace,
+    TokenKind::Other,
+    TokenKind::Quote,
+    TokenKind::EOF,
+  ]);
+}
+
+#[test]
+fn lexer_test_ellipsis() {
+  assert_eq!(test_lex("f(...xs).a"),vec![
+    TokenKind::Alpha,
+    TokenKind::LeftParen,
+    TokenKind::Ellipsis,
+    TokenKind::Alpha,
+    TokenKind::Alpha,
+    TokenKind::RightParen,
+    TokenKind::Dot,
+    TokenKind::Alpha,
+    TokenKind::EOF,
+  ]);
+}
+
+#[test]
+fn lexer_test_const_and_operators() {
+  assert_eq!(test_lex("const a = 6 * 2 / 3;"),vec![
+    TokenKind::Const,
+    TokenKind::Alpha,
+    TokenKind::Equal,
+    TokenKind::Digit,
+    TokenKind::Asterisk,
+    TokenKind::Digit,
+    TokenKind::Slash,
+    TokenKind::Digit,
+    TokenKind::Semicolon,
+    TokenKind::EOF,
+  ]);
+}
+
+#[test]
+fn lexer_test_nil() {
+  assert_eq!(test_lex("let x = nil;"),vec![
+    TokenKind::Let,
+    TokenKind::Alpha,
+    TokenKind::Equal,
+    TokenKind::Nil,
+    TokenKind::Semicolon,
+    TokenKind::EOF,
+  ]);
+}
@@ -20,44 +20,45 @@ macro_rules! test {
   )
 }
 // test name, test string, combinator,  expected result
-test!(parser_ident, r#"hello"#, identifier, Identifier{value: vec![104, 101, 108, 108, 111]});
+test!(parser_ident, r#"hello"#, identifier, Identifier{value: vec![104, 101, 108, 108, 111], span: Span::new(1, 1, 1, 5)});
 test!(parser_number, r#"123"#, number, Number{value: 123});
+test!(parser_bigint, r#"12345678901234567890n"#, number, Node::BigInt{value: "12345678901234567890".parse::<asalang::BigInt>().unwrap()});
 test!(parser_bool, r#"true"#, boolean, Bool{value: true});
 test!(parser_string, r#""hello""#, string, String{value: "hello".to_string()});
-test!(parser_function_call, r#"foo()"#, function_call, FunctionCall{name: vec![102, 111, 111], children: vec![
+test!(parser_function_call, r#"foo()"#, function_call, FunctionCall{name: vec![102, 111, 111], span: Span::new(1, 1, 1, 5), children: vec![
   FunctionArguments{ children: vec![
   ]}
 ]});
-test!(parser_function_call_one_arg, r#"foo(a)"#, function_call, FunctionCall{name: vec![102, 111, 111], children: vec![
+test!(parser_function_call_one_arg, r#"foo(a)"#, function_call, FunctionCall{name: vec![102, 111, 111], span: Span::new(1, 1, 1, 6), children: vec![
   FunctionArguments{ children: vec![
-    Expression { children: vec![Identifier { value: vec![97] }]}
+    Expression { children: vec![Identifier { value: vec![97], span: Span::new(1, 5, 1, 5) }]}
   ]}
 ]});
 test!(parser_variable_define_number, r#"let a = 123"#, variable_define, VariableDefine{children: vec![
-  Identifier { value: vec![97] },
+  Identifier { value: vec![97], span: Span::new(1, 5, 1, 5) },
   Expression { children: vec![Number{value: 123 }]}
 ]});
 test!(parser_variable_define_bool, r#"let a = true"#, variable_define, VariableDefine{children: vec![
-  Identifier { value: vec![97] },
+  Identifier { value: vec![97], span: Span::new(1, 5, 1, 5) },
   Expression { children: vec![Bool{value: true}]}
 ]});
-test!(parser_math_expr, r#"1+1"#, math_expression, MathExpression {name: vec![97, 100, 100], children: vec![
+test!(parser_math_expr, r#"1+1"#, math_expression, MathExpression {name: vec![97, 100, 100], span: Span::new(1, 2, 1, 2), children: vec![
   Number{value: 1},
   Number{value: 1}
 ]});
 test!(parser_variable_define_math_expr, r#"let a = 1 + 1"#, variable_define, VariableDefine{children: vec![
-  Identifier { value: vec![97] },
+  Identifier { value: vec![97], span: Span::new(1, 5, 1, 5) },
   Expression { children: vec![
-    MathExpression {name: vec![97, 100, 100], children: vec![
+    MathExpression {name: vec![97, 100, 100], span: Span::new(1, 11, 1, 11), children: vec![
       Number{value: 1},
       Number{value: 1}
     ]}
   ]}
 ]});
 test!(parser_variable_function_call, r#"let a = foo()"#, variable_define, VariableDefine{children: vec![
-  Identifier { value: vec![97] },
+  Identifier { value: vec![97], span: Span::new(1, 5, 1, 5) },
   Expression { children: vec![
-    FunctionCall{name: vec![102, 111, 111], children: vec![
+    FunctionCall{name: vec![102, 111, 111], span: Span::new(1, 9, 1, 13), children: vec![
       FunctionArguments{ children: vec![
       ]}
     ]}
@@ -78,21 +79,21 @@ test!(parser_function_define_multi_statements, r#"fn add(a,b){let x=a+b;return x
   name: vec![97, 100, 100],
   children: vec![
     FunctionArguments{ children: vec![
-      Expression { children: vec![Identifier { value: vec![97] }] },
-      Expression { children: vec![Identifier { value: vec![98] }] },
+      Expression { children: vec![Identifier { value: vec![97], span: Span::new(1, 8, 1, 8) }] },
+      Expression { children: vec![Identifier { value: vec![98], span: Span::new(1, 10, 1, 10) }] },
     ] },
     FunctionStatements{ children: vec![
       VariableDefine{children: vec![
-        Identifier { value: vec![120] },
+        Identifier { value: vec![120], span: Span::new(1, 17, 1, 17) },
         Expression { children: vec![
-          MathExpression {name: vec![97, 100, 100], children: vec![
-            Identifier{value: vec![97]},
-            Identifier{value: vec![98]}
+          MathExpression {name: vec![97, 100, 100], span: Span::new(1, 20, 1, 20), children: vec![
+            Identifier{value: vec![97], span: Span::new(1, 19, 1, 19)},
+            Identifier{value: vec![98], span: Span::new(1, 21, 1, 21)}
           ]}
         ]}
       ]},
       FunctionReturn{ children: vec![ 
-        Expression { children: vec![Identifier{value: vec![120] }]}
+        Expression { children: vec![Identifier{value: vec![120], span: Span::new(1, 30, 1, 30) }]}
       ]}
     ]}
   ]
@@ -101,7 +102,7 @@ test!(parser_function_define_multi_statements, r#"fn add(a,b){let x=a+b;return x
 // Conditional tests
 test!(parser_conditional_less_than_from_expression, r#"1 < 2"#, expression, 
 Expression { children: vec![
-  ConditionalExpression {name: vec![108, 116, 95], children: vec![
+  ConditionalExpression {name: vec![108, 116, 95], span: Span::new(1, 3, 1, 3), children: vec![
     Expression { children: vec![Number { value: 1 }] },
     Expression { children: vec![Number { value: 2 }] },
   ]}
@@ -109,13 +110,306 @@ Expression { children: vec![
 
 test!(parser_conditional_nested_less_than, r#"1 < 2 == true"#, expression, 
 Expression { children: vec![
-  ConditionalExpression { name: b"eq_".to_vec(), children: vec![
+  ConditionalExpression { name: b"eq_".to_vec(), span: Span::new(1, 7, 1, 8), children: vec![
     Expression { children: vec![
-      ConditionalExpression { name: b"lt_".to_vec(), children: vec![
+      ConditionalExpression { name: b"lt_".to_vec(), span: Span::new(1, 3, 1, 3), children: vec![
         Expression { children: vec![Node::Number { value: 1 }] },
         Expression { children: vec![Node::Number { value: 2 }] },
       ]}
     ]},
     Expression { children: vec![Node::Bool { value: true }] },
   ]}
-]});
\ No newline at end of file
+]});
+// Block tests
+test!(parser_block, r#"{let x = 1; x}"#, block, Block{children: vec![
+  VariableDefine{children: vec![
+    Identifier { value: vec![120], span: Span::new(1, 6, 1, 6) },
+    Expression { children: vec![Number{value: 1}]}
+  ]},
+  Expression { children: vec![Identifier { value: vec![120], span: Span::new(1, 13, 1, 13) }]}
+]});
+test!(parser_variable_define_block, r#"let a = {1}"#, variable_define, VariableDefine{children: vec![
+  Identifier { value: vec![97], span: Span::new(1, 5, 1, 5) },
+  Expression { children: vec![
+    Block{children: vec![
+      Expression { children: vec![Number{value: 1}]}
+    ]}
+  ]}
+]});
+
+// List tests
+test!(parser_list, r#"[1, a]"#, list, List{children: vec![
+  Expression { children: vec![Number{value: 1}]},
+  Expression { children: vec![Identifier { value: vec![97], span: Span::new(1, 5, 1, 5) }]}
+]});
+test!(parser_list_empty, r#"[]"#, list, List{children: vec![]});
+test!(parser_index, r#"xs[0][1]"#, postfix_expression, Index{children: vec![
+  Index{children: vec![
+    Identifier { value: vec![120, 115], span: Span::new(1, 1, 1, 2) },
+    Expression { children: vec![Number{value: 0}]}
+  ]},
+  Expression { children: vec![Number{value: 1}]}
+]});
+test!(parser_index_assignment, r#"xs[0] = 5"#, assignment, Assignment{children: vec![
+  Index{children: vec![
+    Identifier { value: vec![120, 115], span: Span::new(1, 1, 1, 2) },
+    Expression { children: vec![Number{value: 0}]}
+  ]},
+  Expression { children: vec![Number{value: 5}]}
+]});
+
+// Map tests
+test!(parser_map, r#"{"a": 1, "b": x}"#, map, Map{children: vec![
+  KeyValue{children: vec![
+    String{value: "a".to_string()},
+    Expression { children: vec![Number{value: 1}]}
+  ]},
+  KeyValue{children: vec![
+    String{value: "b".to_string()},
+    Expression { children: vec![Identifier { value: vec![120], span: Span::new(1, 15, 1, 15) }]}
+  ]}
+]});
+test!(parser_map_in_expression, r#"{"a": 1}"#, expression, Expression{children: vec![
+  Map{children: vec![
+    KeyValue{children: vec![
+      String{value: "a".to_string()},
+      Expression { children: vec![Number{value: 1}]}
+    ]}
+  ]}
+]});
+test!(parser_function_define_body_not_map, r#"fn a(){{1}}"#, function_define, FunctionDefine{
+  name: vec![97],
+  children: vec![
+    FunctionArguments{ children: vec![] },
+    FunctionStatements{ children: vec![
+      Expression { children: vec![Block{children: vec![
+        Expression { children: vec![Number{value: 1}]}
+      ]}]}
+    ]}
+  ]
+});
+
+// Struct tests
+test!(parser_struct_define, r#"struct P { x, y }"#, struct_define, StructDefine{name: vec![80], children: vec![
+  Identifier { value: vec![120], span: Span::new(1, 12, 1, 12) },
+  Identifier { value: vec![121], span: Span::new(1, 15, 1, 15) },
+]});
+test!(parser_struct_literal, r#"P { x: 1 }"#, struct_literal, StructLiteral{name: vec![80], children: vec![
+  KeyValue{children: vec![
+    Identifier { value: vec![120], span: Span::new(1, 5, 1, 5) },
+    Expression { children: vec![Number{value: 1}]}
+  ]}
+]});
+test!(parser_field_access, r#"p.x"#, postfix_expression, FieldAccess{name: vec![120], children: vec![
+  Identifier { value: vec![112], span: Span::new(1, 1, 1, 1) },
+]});
+test!(parser_field_assignment, r#"p.x = 2"#, assignment, Assignment{children: vec![
+  FieldAccess{name: vec![120], children: vec![
+    Identifier { value: vec![112], span: Span::new(1, 1, 1, 1) },
+  ]},
+  Expression { children: vec![Number{value: 2}]}
+]});
+
+// Enum and match tests
+test!(parser_enum_define, r#"enum Shape { Circle(r), Rect(w, h), Empty }"#, enum_define, EnumDefine{name: b"Shape".to_vec(), children: vec![
+  EnumVariant{name: b"Circle".to_vec(), children: vec![Identifier { value: vec![114], span: Span::new(1, 21, 1, 21) }]},
+  EnumVariant{name: b"Rect".to_vec(), children: vec![Identifier { value: vec![119], span: Span::new(1, 30, 1, 30) }, Identifier { value: vec![104], span: Span::new(1, 33, 1, 33) }]},
+  EnumVariant{name: b"Empty".to_vec(), children: vec![]},
+]});
+test!(parser_match, r#"match s { Circle(r) if r > 1 => r, _ => 0 }"#, match_expression, Match{span: Span::new(1, 1, 1, 43), children: vec![
+  Expression { children: vec![Identifier { value: vec![115], span: Span::new(1, 7, 1, 7) }]},
+  MatchArm{children: vec![
+    VariantPattern{name: b"Circle".to_vec(), children: vec![Identifier { value: vec![114], span: Span::new(1, 18, 1, 18) }]},
+    MatchGuard{children: vec![
+      Expression { children: vec![
+        ConditionalExpression {name: b"gt_".to_vec(), span: Span::new(1, 26, 1, 26), children: vec![
+          Expression { children: vec![Identifier { value: vec![114], span: Span::new(1, 24, 1, 24) }] },
+          Expression { children: vec![Number { value: 1 }] },
+        ]}
+      ]}
+    ]},
+    Expression { children: vec![Identifier { value: vec![114], span: Span::new(1, 33, 1, 33) }]},
+  ]},
+  MatchArm{children: vec![
+    Wildcard,
+    Expression { children: vec![Number { value: 0 }]},
+  ]},
+]});
+
+// Closure tests
+test!(parser_lambda, r#"fn(x){x}"#, lambda, Lambda{children: vec![
+  FunctionArguments{ children: vec![
+    Expression { children: vec![Identifier { value: vec![120], span: Span::new(1, 4, 1, 4) }] },
+  ]},
+  FunctionStatements{ children: vec![
+    Expression { children: vec![Identifier { value: vec![120], span: Span::new(1, 7, 1, 7) }]}
+  ]}
+]});
+test!(parser_call_result, r#"f(1)(2)"#, postfix_expression, Call{children: vec![
+  FunctionCall{name: vec![102], span: Span::new(1, 1, 1, 4), children: vec![
+    FunctionArguments{ children: vec![
+      Expression { children: vec![Number { value: 1 }]}
+    ]}
+  ]},
+  FunctionArguments{ children: vec![
+    Expression { children: vec![Number { value: 2 }]}
+  ]}
+]});
+test!(parser_nested_function_define, r#"fn a(){fn b(){return 1;} return b();}"#, function_define, FunctionDefine{
+  name: vec![97],
+  children: vec![
+    FunctionArguments{ children: vec![] },
+    FunctionStatements{ children: vec![
+      FunctionDefine{
+        name: vec![98],
+        children: vec![
+          FunctionArguments{ children: vec![] },
+          FunctionStatements{ children: vec![
+            FunctionReturn{ children: vec![
+              Expression { children: vec![Number{value: 1 }]}
+            ]}
+          ]}
+        ]
+      },
+      FunctionReturn{ children: vec![
+        Expression { children: vec![
+          FunctionCall{name: vec![98], span: Span::new(1, 33, 1, 35), children: vec![FunctionArguments{ children: vec![] }]}
+        ]}
+      ]}
+    ]}
+  ]
+});
+
+// Import tests
+test!(parser_import, r#"import "lib/math.asa";"#, import, Import{name: b"lib/math.asa".to_vec(), children: vec![]});
+test!(parser_import_as, r#"import "util.asa" as u;"#, import, Import{name: b"util.asa".to_vec(), children: vec![
+  Identifier { value: vec![117], span: Span::new(1, 22, 1, 22) },
+]});
+
+// Type annotation tests
+test!(parser_annotated_function, r#"fn f(a: int, b) -> bool {a}"#, function_define, FunctionDefine{
+  name: vec![102],
+  children: vec![
+    FunctionArguments{ children: vec![
+      Expression { children: vec![Identifier { value: vec![97], span: Span::new(1, 6, 1, 6) }, TypeAnnotation { name: b"int".to_vec(), children: vec![], span: Span::new(1, 9, 1, 11) }] },
+      Expression { children: vec![Identifier { value: vec![98], span: Span::new(1, 14, 1, 14) }] },
+    ]},
+    FunctionStatements{ children: vec![
+      Expression { children: vec![Identifier { value: vec![97], span: Span::new(1, 26, 1, 26) }]}
+    ]},
+    TypeAnnotation { name: b"bool".to_vec(), children: vec![], span: Span::new(1, 20, 1, 23) },
+  ]
+});
+test!(parser_annotated_variable_define, r#"let a: string = "x""#, variable_define, VariableDefine{children: vec![
+  Identifier { value: vec![97], span: Span::new(1, 5, 1, 5) },
+  Expression { children: vec![String{value: "x".to_string()}]},
+  TypeAnnotation { name: b"string".to_vec(), children: vec![], span: Span::new(1, 8, 1, 13) },
+]});
+test!(parser_generic_function, r#"fn first<T>(xs: list<T>) -> T {xs[0]}"#, function_define, FunctionDefine{
+  name: b"first".to_vec(),
+  children: vec![
+    FunctionArguments{ children: vec![
+      Expression { children: vec![
+        Identifier { value: b"xs".to_vec(), span: Span::new(1, 13, 1, 14) },
+        TypeAnnotation { name: b"list".to_vec(), span: Span::new(1, 17, 1, 23), children: vec![
+          TypeAnnotation { name: b"T".to_vec(), children: vec![], span: Span::new(1, 22, 1, 22) },
+        ]},
+      ]},
+    ]},
+    FunctionStatements{ children: vec![
+      Expression { children: vec![Index{children: vec![
+        Identifier { value: b"xs".to_vec(), span: Span::new(1, 32, 1, 33) },
+        Expression { children: vec![Number{value: 0}]},
+      ]}]}
+    ]},
+    TypeAnnotation { name: b"T".to_vec(), children: vec![], span: Span::new(1, 29, 1, 29) },
+    TypeParameters { children: vec![Identifier { value: b"T".to_vec(), span: Span::new(1, 10, 1, 10) }] },
+  ]
+});
+test!(parser_string_with_spaces, r#""hello world""#, string, String{value: "hello world".to_string()});
+test!(parser_string_empty, r#""""#, string, String{value: "".to_string()});
+test!(parser_string_interpolation, r#""a {x} {{b}}""#, string, Interpolation{children: vec![
+  String{value: "a ".to_string()},
+  Expression{children: vec![Identifier{value: vec![120], span: Span::new(1, 5, 1, 5)}]},
+  String{value: " {b}".to_string()},
+]});
+
+#[test]
+fn parser_string_malformed_interpolation() {
+  assert!(string(lex(r#""a {b""#)).is_err());
+  assert!(string(lex(r#""a {} b""#)).is_err());
+  assert!(string(lex(r#""a } b""#)).is_err());
+}
+test!(parser_parameter_default, r#"b: int = a + 1"#, parameter, Expression{children: vec![
+  Identifier { value: vec![98], span: Span::new(1, 1, 1, 1) },
+  TypeAnnotation { name: b"int".to_vec(), children: vec![], span: Span::new(1, 4, 1, 6) },
+  Expression { children: vec![MathExpression{name: b"add".to_vec(), span: Span::new(1, 12, 1, 12), children: vec![
+    Identifier { value: vec![97], span: Span::new(1, 10, 1, 10) },
+    Number { value: 1 },
+  ]}]},
+]});
+test!(parser_named_argument, r#"f(a, b: 1)"#, function_call, FunctionCall{name: vec![102], span: Span::new(1, 1, 1, 10), children: vec![
+  FunctionArguments{ children: vec![
+    Expression { children: vec![Identifier { value: vec![97], span: Span::new(1, 3, 1, 3) }]},
+    NamedArgument { name: vec![98], span: Span::new(1, 6, 1, 9), children: vec![
+      Expression { children: vec![Number { value: 1 }]},
+    ]},
+  ]}
+]});
+test!(parser_rest_parameter, r#"level, ...parts: list<string>"#, parameters, FunctionArguments{children: vec![
+  Expression { children: vec![Identifier { value: b"level".to_vec(), span: Span::new(1, 1, 1, 5) }]},
+  Spread { children: vec![
+    Identifier { value: b"parts".to_vec(), span: Span::new(1, 11, 1, 15) },
+    TypeAnnotation { name: b"list".to_vec(), span: Span::new(1, 18, 1, 29), children: vec![
+      TypeAnnotation { name: b"string".to_vec(), children: vec![], span: Span::new(1, 23, 1, 28) },
+    ]},
+  ]},
+]});
+test!(parser_spread_argument, r#"f(1, ...xs)"#, function_call, FunctionCall{name: vec![102], span: Span::new(1, 1, 1, 11), children: vec![
+  FunctionArguments{ children: vec![
+    Expression { children: vec![Number { value: 1 }]},
+    Spread { children: vec![Expression { children: vec![Identifier { value: b"xs".to_vec(), span: Span::new(1, 9, 1, 10) }]}]},
+  ]}
+]});
+test!(parser_string_keeps_operators, r#""a == b...""#, string, String{value: "a == b...".to_string()});
+test!(parser_math_precedence, r#"1 + 2 * 3"#, math_expression, MathExpression {name: b"add".to_vec(), span: Span::new(1, 3, 1, 3), children: vec![
+  Number { value: 1 },
+  MathExpression {name: b"mul".to_vec(), span: Span::new(1, 7, 1, 7), children: vec![
+    Number { value: 2 },
+    Number { value: 3 },
+  ]},
+]});
+test!(parser_math_left_associative, r#"8 / 4 - 2 - 1"#, math_expression, MathExpression {name: b"sub".to_vec(), span: Span::new(1, 11, 1, 11), children: vec![
+  MathExpression {name: b"sub".to_vec(), span: Span::new(1, 7, 1, 7), children: vec![
+    MathExpression {name: b"div".to_vec(), span: Span::new(1, 3, 1, 3), children: vec![
+      Number { value: 8 },
+      Number { value: 4 },
+    ]},
+    Number { value: 2 },
+  ]},
+  Number { value: 1 },
+]});
+test!(parser_const_define, r#"const LIMIT: int = 10;"#, const_define, ConstDefine{children: vec![
+  Identifier { value: b"LIMIT".to_vec(), span: Span::new(1, 7, 1, 11) },
+  Expression { children: vec![Number{value: 10}]},
+  TypeAnnotation { name: b"int".to_vec(), children: vec![], span: Span::new(1, 14, 1, 16) },
+]});
+test!(parser_try_expression, r#"try { f() } catch (e) { e } finally { g() }"#, try_expression, Try{children: vec![
+  Block { children: vec![Expression { children: vec![FunctionCall{name: vec![102], span: Span::new(1, 7, 1, 9), children: vec![FunctionArguments{children: vec![]}]}]}]},
+  Identifier { value: vec![101], span: Span::new(1, 20, 1, 20) },
+  Block { children: vec![Expression { children: vec![Identifier { value: vec![101], span: Span::new(1, 25, 1, 25) }]}]},
+  Block { children: vec![Expression { children: vec![FunctionCall{name: vec![103], span: Span::new(1, 39, 1, 41), children: vec![FunctionArguments{children: vec![]}]}]}]},
+]});
+test!(parser_throw, r#"throw "bad";"#, statement, Throw{children: vec![
+  Expression { children: vec![String{value: "bad".to_string()}]},
+]});
+test!(parser_nil, r#"nil"#, primary, Null);
+
+#[test]
+fn parser_number_too_large() {
+  assert_eq!(number(lex("2147483648")).map(|(_, tree)| tree), Ok(Node::LargeNumber {
+    value: asalang::BigInt::from(2147483648i64),
+    span: Span::new(1, 1, 1, 10),
+  }));
+  assert_eq!(number(lex("2147483648n")).map(|(_, tree)| tree), Ok(Node::BigInt { value: asalang::BigInt::from(2147483648i64) }));
+}